@@ -0,0 +1,102 @@
+//! Converting the name of a variant into its message, with `#[display(default_case = "...")]`
+
+/// How the words of the name are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    /// `not_found`
+    Snake,
+    /// `not-found`
+    Kebab,
+    /// `Not Found`
+    Title,
+    /// `not found`
+    LowerWords,
+}
+
+impl Case {
+    /// All of the cases, as they are written in `default_case = "..."`
+    pub const NAMES: &'static str = "`snake_case`, `kebab-case`, `Title Case` or `lowercase words`";
+
+    /// Parse the value of `default_case = "..."`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "snake_case" => Some(Self::Snake),
+            "kebab-case" => Some(Self::Kebab),
+            "Title Case" => Some(Self::Title),
+            "lowercase words" => Some(Self::LowerWords),
+            _ => None,
+        }
+    }
+
+    /// Convert the name of a variant, like `NotFound`, to this case
+    pub fn convert(self, name: &str) -> String {
+        let words = words(name.strip_prefix("r#").unwrap_or(name));
+
+        let separator = match self {
+            Self::Snake => "_",
+            Self::Kebab => "-",
+            Self::Title | Self::LowerWords => " ",
+        };
+
+        words
+            .iter()
+            .map(|word| match self {
+                Self::Title => {
+                    let mut chars = word.chars();
+                    chars
+                        .next()
+                        .into_iter()
+                        .flat_map(char::to_uppercase)
+                        .chain(chars.flat_map(char::to_lowercase))
+                        .collect()
+                }
+                Self::Snake | Self::Kebab | Self::LowerWords => word.to_lowercase(),
+            })
+            .collect::<Vec<String>>()
+            .join(separator)
+    }
+}
+
+/// Split the name into words. A new word starts after an underscore, and at each uppercase letter
+/// that follows a lowercase letter or a digit. In a run of uppercase letters, the last one starts a new
+/// word if it is followed by a lowercase letter
+///
+/// ```ignore
+/// "HTTPStatus2Ok" => ["HTTP", "Status2", "Ok"]
+/// ```
+fn words(name: &str) -> Vec<String> {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut words = Vec::new();
+    let mut word = String::new();
+
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch == '_' {
+            if !word.is_empty() {
+                words.push(core::mem::take(&mut word));
+            }
+            continue;
+        }
+
+        if ch.is_uppercase() && !word.is_empty() {
+            let previous = chars[i - 1];
+            let next_is_lowercase = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+
+            // "NotFound", "Status2Ok" or "HTTPStatus"
+            //     ^             ^             ^
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lowercase)
+            {
+                words.push(core::mem::take(&mut word));
+            }
+        }
+
+        word.push(ch);
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
@@ -0,0 +1,367 @@
+//! Parsing the templates, which use the same syntax as [`format_args!`](core::format_args)
+
+use proc_macro::Literal;
+
+/// A part of the format string
+#[derive(Debug)]
+pub enum Piece {
+    /// Text outside of the `{...}`, with `{{` and `}}` unescaped
+    ///
+    /// ```ignore
+    /// "expected {expected}, found {{found}}"
+    ///  ^^^^^^^^^          ^^^^^^^^^^^^^^^^^
+    /// ```
+    Text(String),
+    /// A `{...}`
+    Placeholder(Placeholder),
+}
+
+/// A `{...}` in the format string
+#[derive(Debug)]
+pub struct Placeholder {
+    /// The value that is formatted
+    ///
+    /// ```ignore
+    /// "{expected:>width$?}"
+    ///   ^^^^^^^^
+    /// ```
+    pub argument: Argument,
+    /// Arguments used for the width and precision
+    ///
+    /// ```ignore
+    /// "{expected:>width$.prec$?}"
+    ///             ^^^^^  ^^^^
+    /// ```
+    pub counts: Vec<Argument>,
+    /// The formatting trait
+    ///
+    /// ```ignore
+    /// "{expected:>width$?}"
+    ///                   ^
+    /// ```
+    pub format_trait: FormatTrait,
+    /// Everything after the `:`
+    ///
+    /// ```ignore
+    /// "{expected:>width$?}"
+    ///            ^^^^^^^^
+    /// ```
+    pub spec: String,
+    /// The placeholder exactly as it is written
+    ///
+    /// ```ignore
+    /// "{expected:>width$?}"
+    ///  ^^^^^^^^^^^^^^^^^^^
+    /// ```
+    pub source: String,
+}
+
+/// What a placeholder refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Argument {
+    /// `{0}`, or `{}` which gets resolved to the next index
+    Index(usize),
+    /// `{name}`
+    Name(String),
+}
+
+/// Each of the traits in [`core::fmt`] that can be used for formatting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatTrait {
+    /// `{}`
+    Display,
+    /// `{:?}`
+    Debug,
+    /// `{:x}`
+    LowerHex,
+    /// `{:X}`
+    UpperHex,
+    /// `{:o}`
+    Octal,
+    /// `{:b}`
+    Binary,
+    /// `{:e}`
+    LowerExp,
+    /// `{:E}`
+    UpperExp,
+    /// `{:p}`
+    Pointer,
+}
+
+impl FormatTrait {
+    /// Name of the trait in [`core::fmt`]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Display => "Display",
+            Self::Debug => "Debug",
+            Self::LowerHex => "LowerHex",
+            Self::UpperHex => "UpperHex",
+            Self::Octal => "Octal",
+            Self::Binary => "Binary",
+            Self::LowerExp => "LowerExp",
+            Self::UpperExp => "UpperExp",
+            Self::Pointer => "Pointer",
+        }
+    }
+}
+
+/// Parse all of the `{...}` in a format string.
+///
+/// We don't need to report every error here, as `format_args!` will do that for us.
+/// If the string is invalid, we just return `None`
+pub fn placeholders(string: &str) -> Option<Vec<Placeholder>> {
+    Some(
+        pieces(string)?
+            .into_iter()
+            .filter_map(|piece| match piece {
+                Piece::Placeholder(placeholder) => Some(placeholder),
+                Piece::Text(_) => None,
+            })
+            .collect(),
+    )
+}
+
+/// Split the format string into the text and the `{...}`. Consecutive text is
+/// always merged into a single [`Piece::Text`]
+///
+/// If the string is invalid, returns `None`
+pub fn pieces(string: &str) -> Option<Vec<Piece>> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = string.chars().peekable();
+
+    // Index of the next argument used by `{}`
+    let mut next_index = 0;
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            // "{{" or "}}"
+            '{' | '}' if chars.peek() == Some(&ch) => {
+                chars.next();
+                text.push(ch);
+            }
+            // "{expected:?}"
+            '{' => {
+                let mut inside = String::new();
+                loop {
+                    match chars.next()? {
+                        '}' => break,
+                        ch => inside.push(ch),
+                    }
+                }
+
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(core::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Placeholder(parse_placeholder(
+                    &inside,
+                    &mut next_index,
+                )?));
+            }
+            // unmatched "}"
+            '}' => return None,
+            ch => text.push(ch),
+        }
+    }
+
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+
+    Some(pieces)
+}
+
+/// Parse the inside of the `{...}`
+///
+/// ```ignore
+/// "{expected:>width$?}"
+///   ^^^^^^^^^^^^^^^^
+/// ```
+fn parse_placeholder(inside: &str, next_index: &mut usize) -> Option<Placeholder> {
+    let (argument, spec) = match inside.split_once(':') {
+        Some((argument, spec)) => (argument.trim(), spec),
+        None => (inside.trim(), ""),
+    };
+    let mut rest = spec;
+
+    let mut counts = Vec::new();
+
+    // [[fill]align]
+    let mut spec_chars = rest.chars();
+    match (spec_chars.next(), spec_chars.next()) {
+        (Some(_), Some('<' | '^' | '>')) => {
+            rest = spec_chars.as_str();
+        }
+        (Some('<' | '^' | '>'), _) => {
+            rest = &rest[1..];
+        }
+        _ => (),
+    }
+
+    // [sign]['#']
+    rest = rest.strip_prefix(['+', '-']).unwrap_or(rest);
+    rest = rest.strip_prefix('#').unwrap_or(rest);
+
+    // ['0'], unless it's the `0$` width
+    if rest.starts_with('0') && !rest[1..].starts_with('$') {
+        rest = &rest[1..];
+    }
+
+    // [width]
+    if let Some((count, after)) = parse_count(rest) {
+        counts.extend(count);
+        rest = after;
+    }
+
+    // ['.' precision]
+    if let Some(after) = rest.strip_prefix('.') {
+        if let Some(after) = after.strip_prefix('*') {
+            // `.*` takes 2 arguments: first the precision, then the value
+            counts.push(Argument::Index(*next_index));
+            *next_index += 1;
+            rest = after;
+        } else {
+            let (count, after) = parse_count(after)?;
+            counts.extend(count);
+            rest = after;
+        }
+    }
+
+    // type
+    let format_trait = match rest {
+        "" => FormatTrait::Display,
+        "?" | "x?" | "X?" => FormatTrait::Debug,
+        "x" => FormatTrait::LowerHex,
+        "X" => FormatTrait::UpperHex,
+        "o" => FormatTrait::Octal,
+        "b" => FormatTrait::Binary,
+        "e" => FormatTrait::LowerExp,
+        "E" => FormatTrait::UpperExp,
+        "p" => FormatTrait::Pointer,
+        _ => return None,
+    };
+
+    let argument = if argument.is_empty() {
+        let index = *next_index;
+        *next_index += 1;
+        Argument::Index(index)
+    } else if let Ok(index) = argument.parse() {
+        Argument::Index(index)
+    } else if is_identifier(argument) {
+        Argument::Name(argument.to_string())
+    } else {
+        return None;
+    };
+
+    Some(Placeholder {
+        argument,
+        counts,
+        format_trait,
+        spec: spec.to_string(),
+        source: format!("{{{inside}}}"),
+    })
+}
+
+/// Parse the width or precision. Returns the argument used, if any, and the rest of the spec
+///
+/// ```ignore
+/// "{:>width$?}"
+///     ^^^^^^
+/// ```
+fn parse_count(spec: &str) -> Option<(Option<Argument>, &str)> {
+    let end = spec
+        .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+        .unwrap_or(spec.len());
+    let (count, rest) = spec.split_at(end);
+
+    if let Some(rest) = rest.strip_prefix('$') {
+        // width$ or 1$
+        let argument = match count.parse() {
+            Ok(index) => Argument::Index(index),
+            Err(_) if is_identifier(count) => Argument::Name(count.to_string()),
+            Err(_) => return None,
+        };
+        Some((Some(argument), rest))
+    } else {
+        // a literal number like `10`. Identifiers in this position are the type, like `x`
+        let digits = spec
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(spec.len());
+        (digits != 0).then(|| (None, &spec[digits..]))
+    }
+}
+
+/// Whether this is a valid identifier, like `foo` or `_0`
+fn is_identifier(string: &str) -> bool {
+    let mut chars = string.chars();
+    chars
+        .next()
+        .is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_alphanumeric() || ch == '_')
+}
+
+/// Obtain the value of a string literal, with all of the escapes processed.
+///
+/// Returns `None` if it is not a string literal
+pub fn string_value(literal: &Literal) -> Option<String> {
+    let source = literal.to_string();
+
+    // r#"foo"#
+    if let Some(raw) = source.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        return raw
+            .get(hashes + 1..raw.len().checked_sub(hashes + 1)?)
+            .map(ToString::to_string);
+    }
+
+    let mut chars = source
+        .strip_prefix('"')?
+        .strip_suffix('"')?
+        .chars()
+        .peekable();
+    let mut value = String::new();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            value.push(ch);
+            continue;
+        }
+
+        match chars.next()? {
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            '\\' => value.push('\\'),
+            '0' => value.push('\0'),
+            '\'' => value.push('\''),
+            '"' => value.push('"'),
+            // \x7f
+            'x' => {
+                let hex = [chars.next()?, chars.next()?].iter().collect::<String>();
+                value.push(char::from(u8::from_str_radix(&hex, 16).ok()?));
+            }
+            // \u{1F600}
+            'u' => {
+                let mut hex = String::new();
+                chars.next()?;
+                loop {
+                    match chars.next()? {
+                        '}' => break,
+                        '_' => (),
+                        ch => hex.push(ch),
+                    }
+                }
+                value.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            }
+            // line continuation, skips all of the whitespace on the next line
+            '\n' | '\r' => {
+                while chars.peek().is_some_and(|ch| ch.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    Some(value)
+}
@@ -0,0 +1,617 @@
+//! Generating the `FromStr` impl, which parses the output of `Display` back into the item
+//!
+//! Each template is split into the text and the `{field}` placeholders. The text has to match exactly,
+//! and each placeholder captures everything up to the next text, which is parsed with the `FromStr`
+//! of the field's type
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use crate::format_string::{self, Argument, FormatTrait, Piece};
+use crate::generics::Generics;
+use crate::{CompileError, Field, Variant};
+
+/// A part of the template, as it is parsed
+///
+/// ```ignore
+/// "invalid header (expected {expected}, found {found})"
+///  ^^^^^^^^^^^^^^^^^^^^^^^^^                            Text
+///                           ^^^^^^^^^^                  Field
+/// ```
+pub enum Part {
+    /// Text that must match exactly
+    Text(String),
+    /// Index of the field parsed from this part
+    Field(usize),
+}
+
+/// Adds the text that is written before and after every message, like `#[display(prefix = "...")]`
+pub fn wrap(prefix: &str, suffix: &str, parts: Vec<Part>) -> Vec<Part> {
+    let mut wrapped = Vec::new();
+    let mut text = prefix.to_string();
+
+    for part in parts {
+        match part {
+            // Consecutive text is merged, so that a field always ends at the text that follows it
+            Part::Text(part) => text.push_str(&part),
+            Part::Field(index) => {
+                if !text.is_empty() {
+                    wrapped.push(Part::Text(core::mem::take(&mut text)));
+                }
+                wrapped.push(Part::Field(index));
+            }
+        }
+    }
+
+    text.push_str(suffix);
+    if !text.is_empty() {
+        wrapped.push(Part::Text(text));
+    }
+
+    wrapped
+}
+
+/// Splits the template into the parts used for parsing it.
+///
+/// Reports an error if the template can't be parsed back into the variant
+pub fn parts(
+    variant: &Variant,
+    string: &Literal,
+    args: &TokenStream,
+) -> Result<Vec<Part>, CompileError> {
+    if !args.is_empty() {
+        return Err(CompileError::new(
+            string.span(),
+            "`from_str` can't parse templates with arguments after the string",
+        ));
+    }
+
+    let Some(pieces) = format_string::string_value(string).and_then(|s| format_string::pieces(&s))
+    else {
+        return Err(CompileError::new(string.span(), "invalid template"));
+    };
+
+    let mut parts = Vec::new();
+
+    for piece in pieces {
+        let placeholder = match piece {
+            Piece::Text(text) => {
+                parts.push(Part::Text(text));
+                continue;
+            }
+            Piece::Placeholder(placeholder) => placeholder,
+        };
+
+        // "{expected}"
+        //   ^^^^^^^^
+        let field = match &placeholder.argument {
+            Argument::Name(name)
+                if placeholder.format_trait == FormatTrait::Display
+                    && placeholder.spec.is_empty() =>
+            {
+                variant
+                    .fields
+                    .iter()
+                    .position(|field| field.binding.to_string() == *name)
+            }
+            _ => None,
+        };
+
+        let Some(field) = field else {
+            return Err(CompileError::new(
+                string.span(),
+                "`from_str` can only parse placeholders that are a field, like `{field}`",
+            ));
+        };
+
+        // "{expected}{found}"
+        //            ^ where does `expected` end?
+        if matches!(parts.last(), Some(Part::Field(_))) {
+            return Err(CompileError::new(
+                string.span(),
+                "`from_str` can't parse placeholders that aren't separated by text",
+            ));
+        }
+
+        parts.push(Part::Field(field));
+    }
+
+    // Every field has to be parsed to construct the variant
+    for (index, field) in variant.fields.iter().enumerate() {
+        if !parts
+            .iter()
+            .any(|part| matches!(part, Part::Field(i) if *i == index))
+        {
+            return Err(CompileError::new(
+                field.span,
+                format!(
+                    "`from_str` can't parse field `{}` because it isn't in the template",
+                    field.binding
+                ),
+            ));
+        }
+    }
+
+    Ok(parts)
+}
+
+/// Generates a block that returns the variant from `from_str` if the string matches
+///
+/// ```ignore
+/// 'variant: {
+///     let rest = s;
+///     let Some(rest) = rest.strip_prefix("invalid header (expected ") else { break 'variant; };
+///     let Some(rest) = rest.strip_suffix(")") else { break 'variant; };
+///     let Some((expected, rest)) = rest.split_once(", found ") else { break 'variant; };
+///     let Ok(expected) = <String as FromStr>::from_str(expected) else { break 'variant; };
+///     let found = rest;
+///     let Ok(found) = <String as FromStr>::from_str(found) else { break 'variant; };
+///     return Ok(Self::InvalidHeader { expected, found, });
+/// }
+/// ```
+pub fn parse_block(
+    variant: Option<&str>,
+    fields: &[Field],
+    delimiter: Delimiter,
+    parts: &[Part],
+) -> TokenStream {
+    let mut block = TokenStream::new();
+
+    // let rest = s;
+    block.extend(let_statement(rest(), TokenStream::from_iter([input()])));
+
+    let mut parts = parts.iter().peekable();
+
+    // "invalid header (expected {expected}, found {found})"
+    //  ^^^^^^^^^^^^^^^^^^^^^^^^^
+    if let Some(Part::Text(prefix)) = parts.peek() {
+        block.extend(let_else(some(rest()), method_call("strip_prefix", prefix)));
+        parts.next();
+    }
+
+    // "invalid header (expected {expected}, found {found})"
+    //                                                     ^
+    if let Some(Part::Text(suffix)) = parts.clone().last() {
+        block.extend(let_else(some(rest()), method_call("strip_suffix", suffix)));
+    }
+
+    // Fields that were already parsed, the same field can appear in the template multiple times
+    let mut parsed = Vec::new();
+
+    while let Some(part) = parts.next() {
+        let Part::Field(index) = part else {
+            // the suffix was already stripped
+            continue;
+        };
+        let field = &fields[*index];
+
+        // The text after the field, if there is one, and that isn't the suffix
+        //
+        // "invalid header (expected {expected}, found {found})"
+        //                                     ^^^^^^^^
+        let remaining = parts.len();
+        let text = match parts.peek() {
+            Some(Part::Text(text)) if remaining > 1 => Some(text.as_str()),
+            _ => None,
+        };
+
+        // Repeated fields are only parsed the first time
+        let binding = if parsed.contains(index) {
+            TokenTree::Ident(Ident::new("_", Span::call_site()))
+        } else {
+            TokenTree::Ident(field.binding.clone())
+        };
+
+        match text {
+            // let Some((expected, rest)) = rest.split_once(", found ") else { break 'variant; };
+            Some(text) => {
+                block.extend(let_else(
+                    some(TokenTree::Group(Group::new(
+                        Delimiter::Parenthesis,
+                        TokenStream::from_iter([
+                            binding.clone(),
+                            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+                            rest(),
+                        ]),
+                    ))),
+                    method_call("split_once", text),
+                ));
+                parts.next();
+            }
+            // let found = rest;
+            None => {
+                block.extend(let_statement(
+                    binding.clone(),
+                    TokenStream::from_iter([rest()]),
+                ));
+            }
+        }
+
+        if parsed.contains(index) {
+            continue;
+        }
+        parsed.push(*index);
+
+        // let Ok(expected) = <String as ::core::str::FromStr>::from_str(expected) else { break 'variant; };
+        let mut value = TokenStream::from_iter([TokenTree::Punct(Punct::new('<', Spacing::Alone))]);
+        value.extend(field.ty.clone());
+        value.extend([TokenTree::Ident(Ident::new("as", Span::call_site()))]);
+        value.extend(from_str_path());
+        value.extend([
+            TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("from_str", Span::call_site())),
+            TokenTree::Group(Group::new(
+                Delimiter::Parenthesis,
+                TokenStream::from_iter([TokenTree::Ident(field.binding.clone())]),
+            )),
+        ]);
+
+        block.extend(let_else(
+            result_variant("Ok", TokenTree::Ident(field.binding.clone())),
+            value,
+        ));
+    }
+
+    // "unknown data store error"
+    //
+    // Nothing was parsed, so all of the string must have been the text
+    if parsed.is_empty() {
+        // if !rest.is_empty() { break 'variant; }
+        block.extend([
+            TokenTree::Ident(Ident::new("if", Span::call_site())),
+            TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+            rest(),
+            TokenTree::Punct(Punct::new('.', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("is_empty", Span::call_site())),
+            TokenTree::Group(Group::new(Delimiter::Parenthesis, TokenStream::new())),
+            TokenTree::Group(Group::new(Delimiter::Brace, break_label())),
+        ]);
+    }
+
+    // return Ok(Self::InvalidHeader { expected, found, });
+    let mut construct = crate::variant_path(variant);
+    construct.extend([TokenTree::Group(Group::new(
+        delimiter,
+        fields
+            .iter()
+            .flat_map(|field| {
+                [
+                    TokenTree::Ident(field.binding.clone()),
+                    TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+                ]
+            })
+            .collect(),
+    ))]);
+
+    block.extend([TokenTree::Ident(Ident::new("return", Span::call_site()))]);
+    block.extend(result_variant(
+        "Ok",
+        TokenTree::Group(Group::new(Delimiter::None, construct)),
+    ));
+    block.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+
+    // 'variant: { ... }
+    TokenStream::from_iter([
+        TokenTree::Punct(Punct::new('\'', Spacing::Joint)),
+        TokenTree::Ident(Ident::new("variant", Span::mixed_site())),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Brace, block)),
+    ])
+}
+
+/// Implementation of the `FromStr` trait, and the error that it returns
+///
+/// Equivalent to:
+///
+/// ```ignore
+/// quote! {
+///     #[doc = "..."]
+///     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///     #vis struct #error_ident {
+///         #[doc = "..."]
+///         #vis variants: &'static [&'static str],
+///     }
+///
+///     impl ::core::fmt::Display for #error_ident { ... }
+///     impl ::std::error::Error for #error_ident {}
+///
+///     impl #impl_generics ::core::str::FromStr for #ident #type_generics #where_clause #bounds {
+///         type Err = #error_ident;
+///
+///         fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+///             #blocks
+///             ::core::result::Result::Err(#error_ident { variants: &[#variants] })
+///         }
+///     }
+/// }
+/// ```
+pub fn from_str_impl(
+    ident: &Ident,
+    visibility: TokenStream,
+    generics: &Generics,
+    bounds: TokenStream,
+    blocks: TokenStream,
+    variants: &[String],
+    error: bool,
+) -> TokenStream {
+    let error_ident = Ident::new(&format!("Parse{ident}Error"), ident.span());
+    let error_generics = Generics::new(TokenStream::new(), TokenStream::new());
+
+    let message = format!(
+        "failed to parse `{ident}`, expected one of: {}",
+        variants.join(", ")
+    );
+
+    let mut output = TokenStream::new();
+
+    // pub struct ParseFooError { pub variants: &'static [&'static str], }
+    output.extend(crate::doc_comment(&format!(
+        "Error returned when parsing [`{ident}`] from a string fails"
+    )));
+    output.extend([
+        TokenTree::Punct(Punct::new('#', Spacing::Joint)),
+        TokenTree::Group(Group::new(
+            Delimiter::Bracket,
+            TokenStream::from_iter([
+                TokenTree::Ident(Ident::new("derive", Span::call_site())),
+                TokenTree::Group(Group::new(
+                    Delimiter::Parenthesis,
+                    [
+                        crate::fmt_path("Debug").to_vec(),
+                        core_path("clone", "Clone"),
+                        core_path("marker", "Copy"),
+                        core_path("cmp", "PartialEq"),
+                        core_path("cmp", "Eq"),
+                    ]
+                    .into_iter()
+                    .flat_map(|path| {
+                        path.into_iter()
+                            .chain([TokenTree::Punct(Punct::new(',', Spacing::Alone))])
+                    })
+                    .collect(),
+                )),
+            ]),
+        )),
+    ]);
+    output.extend(visibility.clone());
+    output.extend([
+        TokenTree::Ident(Ident::new("struct", Span::call_site())),
+        TokenTree::Ident(error_ident.clone()),
+    ]);
+
+    let mut fields = TokenStream::new();
+    fields.extend(crate::doc_comment(
+        "All of the variants that were tried, in order",
+    ));
+    fields.extend(visibility);
+    fields.extend([
+        TokenTree::Ident(Ident::new("variants", Span::call_site())),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+        TokenTree::Punct(Punct::new('\'', Spacing::Joint)),
+        TokenTree::Ident(Ident::new("static", Span::call_site())),
+        TokenTree::Group(Group::new(
+            Delimiter::Bracket,
+            TokenStream::from_iter([
+                TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+                TokenTree::Punct(Punct::new('\'', Spacing::Joint)),
+                TokenTree::Ident(Ident::new("static", Span::call_site())),
+                TokenTree::Ident(Ident::new("str", Span::call_site())),
+            ]),
+        )),
+        TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+    ]);
+    output.extend([TokenTree::Group(Group::new(Delimiter::Brace, fields))]);
+
+    // impl ::core::fmt::Display for ParseFooError { ... }
+    output.extend(crate::fmt_impl(
+        "Display",
+        &error_ident,
+        &error_generics,
+        TokenStream::new(),
+        TokenStream::from_iter([
+            TokenTree::Ident(Ident::new("_", Span::call_site())),
+            TokenTree::Punct(Punct::new('=', Spacing::Joint)),
+            TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("f", Span::call_site())),
+            TokenTree::Punct(Punct::new('.', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("write_str", Span::call_site())),
+            TokenTree::Group(Group::new(
+                Delimiter::Parenthesis,
+                TokenStream::from_iter([TokenTree::Literal(Literal::string(&message))]),
+            )),
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        ]),
+        false,
+    ));
+
+    // impl ::std::error::Error for ParseFooError {}
+    //
+    // `std` isn't available in `no_std` crates, so it is only implemented when the item
+    // implements `Error` too, or when `core` has it
+    if error || cfg!(feature = "core-error") {
+        output.extend(error_generics.impl_block(
+            crate::error_path().into_iter().collect(),
+            &error_ident,
+            TokenStream::new(),
+            TokenStream::new(),
+        ));
+    }
+
+    // type Err = ParseFooError;
+    let mut body = TokenStream::from_iter([
+        TokenTree::Ident(Ident::new("type", Span::call_site())),
+        TokenTree::Ident(Ident::new("Err", Span::call_site())),
+        TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+        TokenTree::Ident(error_ident.clone()),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+
+    // Err(ParseFooError { variants: &["A", "B"] })
+    let mut error = TokenStream::from_iter([
+        TokenTree::Ident(error_ident),
+        TokenTree::Group(Group::new(
+            Delimiter::Brace,
+            TokenStream::from_iter([
+                TokenTree::Ident(Ident::new("variants", Span::call_site())),
+                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+                TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+                TokenTree::Group(Group::new(
+                    Delimiter::Bracket,
+                    variants
+                        .iter()
+                        .flat_map(|variant| {
+                            [
+                                TokenTree::Literal(Literal::string(variant)),
+                                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+                            ]
+                        })
+                        .collect(),
+                )),
+            ]),
+        )),
+    ]);
+    error = result_variant("Err", TokenTree::Group(Group::new(Delimiter::None, error)));
+
+    // fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> { ... }
+    body.extend([
+        TokenTree::Ident(Ident::new("fn", Span::call_site())),
+        TokenTree::Ident(Ident::new("from_str", Span::call_site())),
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::from_iter([
+                input(),
+                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+                TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+                TokenTree::Ident(Ident::new("str", Span::call_site())),
+            ]),
+        )),
+        TokenTree::Punct(Punct::new('-', Spacing::Joint)),
+        TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+    ]);
+    body.extend(core_path("result", "Result"));
+    body.extend([
+        TokenTree::Punct(Punct::new('<', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("Self", Span::call_site())),
+        TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("Self", Span::call_site())),
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("Err", Span::call_site())),
+        TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+        TokenTree::Group(Group::new(
+            Delimiter::Brace,
+            blocks.into_iter().chain(error).collect(),
+        )),
+    ]);
+
+    output.extend(generics.impl_block(from_str_path().into_iter().collect(), ident, bounds, body));
+
+    output
+}
+
+/// `::core::str::FromStr`
+pub fn from_str_path() -> Vec<TokenTree> {
+    core_path("str", "FromStr")
+}
+
+/// `::core::$module::$name`
+fn core_path(module: &str, name: &str) -> Vec<TokenTree> {
+    vec![
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("core", Span::call_site())),
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new(module, Span::call_site())),
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new(name, Span::call_site())),
+    ]
+}
+
+/// The string passed to `from_str`. Uses `mixed_site` so it can't conflict with the fields
+fn input() -> TokenTree {
+    TokenTree::Ident(Ident::new("s", Span::mixed_site()))
+}
+
+/// The rest of the string that is not parsed yet. Uses `mixed_site` so it can't conflict with the fields
+fn rest() -> TokenTree {
+    TokenTree::Ident(Ident::new("rest", Span::mixed_site()))
+}
+
+/// `break 'variant;`
+fn break_label() -> TokenStream {
+    TokenStream::from_iter([
+        TokenTree::Ident(Ident::new("break", Span::call_site())),
+        TokenTree::Punct(Punct::new('\'', Spacing::Joint)),
+        TokenTree::Ident(Ident::new("variant", Span::mixed_site())),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ])
+}
+
+/// `let $pattern = $value;`
+fn let_statement(pattern: TokenTree, value: TokenStream) -> TokenStream {
+    let mut statement = TokenStream::from_iter([
+        TokenTree::Ident(Ident::new("let", Span::call_site())),
+        pattern,
+        TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+    ]);
+    statement.extend(value);
+    statement.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+    statement
+}
+
+/// `let $pattern = $value else { break 'variant; };`
+fn let_else(pattern: TokenStream, value: TokenStream) -> TokenStream {
+    let mut statement =
+        TokenStream::from_iter([TokenTree::Ident(Ident::new("let", Span::call_site()))]);
+    statement.extend(pattern);
+    statement.extend([TokenTree::Punct(Punct::new('=', Spacing::Alone))]);
+    statement.extend(value);
+    statement.extend([
+        TokenTree::Ident(Ident::new("else", Span::call_site())),
+        TokenTree::Group(Group::new(Delimiter::Brace, break_label())),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+    statement
+}
+
+/// `rest.$method("text")`
+fn method_call(method: &str, text: &str) -> TokenStream {
+    TokenStream::from_iter([
+        rest(),
+        TokenTree::Punct(Punct::new('.', Spacing::Alone)),
+        TokenTree::Ident(Ident::new(method, Span::call_site())),
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::from_iter([TokenTree::Literal(Literal::string(text))]),
+        )),
+    ])
+}
+
+/// `::core::option::Option::Some($value)`
+fn some(value: TokenTree) -> TokenStream {
+    let mut some = crate::option_variant("Some");
+    some.extend([TokenTree::Group(Group::new(
+        Delimiter::Parenthesis,
+        TokenStream::from_iter([value]),
+    ))]);
+    some
+}
+
+/// `::core::result::Result::$variant($value)`
+fn result_variant(variant: &str, value: TokenTree) -> TokenStream {
+    let mut path = core_path("result", "Result");
+    path.extend([
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new(variant, Span::call_site())),
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::from_iter([value]),
+        )),
+    ]);
+    path.into_iter().collect()
+}
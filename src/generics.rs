@@ -0,0 +1,251 @@
+//! Splitting the generics of the item, so they can be used in the `impl`
+
+use proc_macro::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Generics of the item, split up for use in an `impl`
+///
+/// ```ignore
+/// enum Foo<'a, T: Display = u8, const N: usize = 4> where T: Debug { ... }
+/// ```
+pub struct Generics {
+    /// All of the generic parameters, in order
+    pub params: Vec<GenericParam>,
+    /// The `where` clause, including the `where` keyword
+    ///
+    /// ```ignore
+    /// enum Foo<T> where T: Debug { ... }
+    ///             ^^^^^^^^^^^^^^
+    /// ```
+    pub where_clause: TokenStream,
+}
+
+/// A single generic parameter
+///
+/// ```ignore
+/// enum Foo<'a, T: Display = u8, const N: usize = 4> { ... }
+///          ^^  ^^^^^^^^^^^^^^^  ^^^^^^^^^^^^^^^^^^ each of these
+/// ```
+pub struct GenericParam {
+    /// Whether this is a lifetime, type or const parameter
+    pub kind: GenericParamKind,
+    /// The parameter as it appears in `impl<...>`, without the default
+    ///
+    /// ```ignore
+    /// enum Foo<T: Display = u8> { ... }
+    ///          ^^^^^^^^^^
+    /// ```
+    pub declaration: TokenStream,
+    /// The parameter as it appears in `Foo<...>`
+    ///
+    /// ```ignore
+    /// enum Foo<'a, T: Display = u8, const N: usize = 4> { ... }
+    ///          ^^  ^                      ^
+    /// ```
+    pub name: TokenStream,
+}
+
+/// The kind of a [`GenericParam`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenericParamKind {
+    /// `'a`
+    Lifetime,
+    /// `T`
+    Type,
+    /// `const N: usize`
+    Const,
+}
+
+impl Generics {
+    /// Parse the generics. `generics` includes the surrounding `<` and `>`, and
+    /// `where_clause` includes the `where` keyword. Both may be empty.
+    pub fn new(generics: TokenStream, where_clause: TokenStream) -> Self {
+        let mut tokens = generics.into_iter().collect::<Vec<_>>();
+
+        // <all: of_the_generics>
+        // ^                    ^ remove these
+        if !tokens.is_empty() {
+            tokens.remove(0);
+            tokens.pop();
+        }
+
+        let params = crate::split_commas(tokens.into_iter().collect())
+            .into_iter()
+            .map(GenericParam::new)
+            .collect();
+
+        Self {
+            params,
+            where_clause,
+        }
+    }
+
+    /// Generates an `impl` block for the item
+    ///
+    /// ```ignore
+    /// impl<'a, T: Display, const N: usize> ::core::fmt::Display for Foo<'a, T, N> where T: Debug { ... }
+    ///     ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ declarations
+    ///                                      ^^^^^^^^^^^^^^^^^^^^ trait_path
+    ///                                                               ^^^ ident
+    ///                                                                  ^^^^^^^^^^ names
+    ///                                                                             ^^^^^^^^^^^^^^ where_clause
+    ///                                                                                            ^^^^^^^ body
+    /// ```
+    ///
+    /// `bounds` are additional predicates added to the `where` clause. Each of them must end with a `,`
+    pub fn impl_block(
+        &self,
+        trait_path: TokenStream,
+        ident: &Ident,
+        bounds: TokenStream,
+        body: TokenStream,
+    ) -> TokenStream {
+        let mut output =
+            TokenStream::from_iter([TokenTree::Ident(Ident::new("impl", Span::call_site()))]);
+
+        // impl<'a, T: Display, const N: usize>
+        //     ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+        output.extend(angle_bracketed(
+            self.params.iter().map(|param| param.declaration.clone()),
+        ));
+
+        // impl ::core::fmt::Display for Foo
+        //      ^^^^^^^^^^^^^^^^^^^^^^^^
+        if !trait_path.is_empty() {
+            output.extend(trait_path);
+            output.extend([TokenTree::Ident(Ident::new("for", Span::call_site()))]);
+        }
+
+        // impl ::core::fmt::Display for Foo<'a, T, N>
+        //                               ^^^^^^^^^^^^^
+        output.extend([TokenTree::Ident(ident.clone())]);
+        output.extend(angle_bracketed(
+            self.params.iter().map(|param| param.name.clone()),
+        ));
+
+        // impl ::core::fmt::Display for Foo where T: Debug { ... }
+        //                                   ^^^^^^^^^^^^^^
+        output.extend(self.where_clause.clone());
+
+        // impl ::core::fmt::Display for Foo where T: Debug, Vec<T>: Display, { ... }
+        //                                                 ^^^^^^^^^^^^^^^^^^
+        if !bounds.is_empty() {
+            match self.where_clause.clone().into_iter().last() {
+                // no `where` clause
+                None => {
+                    output.extend([TokenTree::Ident(Ident::new("where", Span::call_site()))]);
+                }
+                // where T: Debug,
+                //               ^ already has a trailing comma
+                Some(TokenTree::Punct(punct)) if punct == ',' => (),
+                // where T: Debug
+                //               ^ add a comma
+                Some(_) => {
+                    output.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+                }
+            }
+
+            output.extend(bounds);
+        }
+
+        // impl ::core::fmt::Display for Foo { ... }
+        //                                   ^^^^^^^
+        output.extend([TokenTree::Group(Group::new(Delimiter::Brace, body))]);
+
+        output
+    }
+}
+
+impl GenericParam {
+    /// Parse a single generic parameter, with the trailing comma removed
+    fn new(param: Vec<TokenTree>) -> Self {
+        let mut name = TokenStream::new();
+        let kind;
+
+        // Attributes can't appear in the name, so skip them
+        //
+        // #[foo] T: Display
+        // ^^^^^^
+        let mut rest = param.iter().skip_while({
+            let mut is_attribute = false;
+            move |tt| match tt {
+                TokenTree::Punct(punct) if *punct == '#' => {
+                    is_attribute = true;
+                    true
+                }
+                TokenTree::Group(group)
+                    if is_attribute && group.delimiter() == Delimiter::Bracket =>
+                {
+                    is_attribute = false;
+                    true
+                }
+                _ => false,
+            }
+        });
+
+        match rest.next() {
+            // 'a: 'b
+            // ^^
+            Some(TokenTree::Punct(punct)) if *punct == '\'' => {
+                kind = GenericParamKind::Lifetime;
+                name.extend([TokenTree::Punct(punct.clone())]);
+                name.extend(rest.next().cloned());
+            }
+            // const N: usize = 4
+            //       ^
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "const" => {
+                kind = GenericParamKind::Const;
+                name.extend(rest.next().cloned());
+            }
+            // T: Display = u8
+            // ^
+            tt => {
+                kind = GenericParamKind::Type;
+                name.extend(tt.cloned());
+            }
+        }
+
+        // Defaults aren't allowed in `impl<...>`, so remove them
+        //
+        // T: Display = u8
+        //            ^^^^ remove this
+        let mut depth = crate::AngleDepth::default();
+        let declaration = param
+            .into_iter()
+            .take_while(|tt| match tt {
+                // T: Iterator<Item = u8>
+                //                  ^ not the default
+                TokenTree::Punct(punct) if *punct == '=' && depth.depth == 0 => false,
+                tt => {
+                    depth.next(tt);
+                    true
+                }
+            })
+            .collect();
+
+        Self {
+            kind,
+            declaration,
+            name,
+        }
+    }
+}
+
+/// `<a, b, c>`, or nothing if there are no items
+fn angle_bracketed(items: impl Iterator<Item = TokenStream>) -> TokenStream {
+    let mut output = TokenStream::new();
+
+    for item in items {
+        output.extend([if output.is_empty() {
+            TokenTree::Punct(Punct::new('<', Spacing::Alone))
+        } else {
+            TokenTree::Punct(Punct::new(',', Spacing::Alone))
+        }]);
+        output.extend(item);
+    }
+
+    if !output.is_empty() {
+        output.extend([TokenTree::Punct(Punct::new('>', Spacing::Alone))]);
+    }
+
+    output
+}
@@ -0,0 +1,584 @@
+//! Padding the whole message with `#[display(pad)]`, so that `{:>20}` and `{:.5}` apply to it
+//!
+//! The message is formatted twice: once into a writer that only counts the characters, and then into
+//! the formatter, surrounded by the fill. This needs no allocation, so it works in `no_std`, and there is
+//! no limit on the length of the message. Width, fill, alignment and precision behave the same as with
+//! [`Formatter::pad`](core::fmt::Formatter::pad)
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Pads the message if the width or precision is set, and returns early. `trait_name` is `Display` or `Debug`
+///
+/// Equivalent to:
+///
+/// ```ignore
+/// quote! {
+///     if f.width().is_some() || f.precision().is_some() {
+///         #writer_struct
+///         #writer_impl
+///         #write_fn
+///
+///         let alternate = f.alternate();
+///         let remaining = f.precision().unwrap_or(::core::primitive::usize::MAX);
+///         let mut counter = Writer { f: ::core::option::Option::None, remaining, count: 0 };
+///         write(&mut counter, self, alternate)?;
+///
+///         let padding = f.width().unwrap_or(0).saturating_sub(counter.count);
+///         let (before, after) = match f.align() {
+///             ::core::option::Option::Some(::core::fmt::Alignment::Right) => (padding, 0),
+///             ::core::option::Option::Some(::core::fmt::Alignment::Center) => (padding / 2, padding - padding / 2),
+///             _ => (0, padding),
+///         };
+///         let fill = f.fill();
+///
+///         for _ in 0..before { ::core::fmt::Write::write_char(f, fill)?; }
+///         write(&mut Writer { f: ::core::option::Option::Some(f), remaining, count: 0 }, self, alternate)?;
+///         for _ in 0..after { ::core::fmt::Write::write_char(f, fill)?; }
+///         return ::core::result::Result::Ok(());
+///     }
+/// }
+/// ```
+pub fn pad(trait_name: &str) -> TokenStream {
+    let mut body = TokenStream::new();
+    body.extend(writer_struct());
+    body.extend(writer_impl());
+    body.extend(write_fn(trait_name));
+
+    // let alternate = f.alternate();
+    body.extend(let_statement(
+        "alternate",
+        [ident("f"), punct('.'), ident("alternate"), empty_parens()],
+    ));
+
+    // let remaining = f.precision().unwrap_or(::core::primitive::usize::MAX);
+    let mut max = core_path(&["primitive", "usize"]);
+    max.extend(colons());
+    max.push(ident("MAX"));
+    body.extend(let_statement(
+        "remaining",
+        [
+            ident("f"),
+            punct('.'),
+            ident("precision"),
+            empty_parens(),
+            punct('.'),
+            ident("unwrap_or"),
+            parens(max),
+        ],
+    ));
+
+    // let mut counter = Writer { f: ::core::option::Option::None, remaining, count: 0 };
+    body.extend([ident("let"), ident("mut"), ident("counter"), punct('=')]);
+    body.extend(writer(crate::option_variant("None")));
+    body.extend([punct(';')]);
+
+    // write(&mut counter, self, alternate)?;
+    body.extend(write_call([punct('&'), ident("mut"), ident("counter")]));
+
+    // let padding = f.width().unwrap_or(0).saturating_sub(counter.count);
+    body.extend(let_statement(
+        "padding",
+        [
+            ident("f"),
+            punct('.'),
+            ident("width"),
+            empty_parens(),
+            punct('.'),
+            ident("unwrap_or"),
+            parens([TokenTree::Literal(Literal::usize_unsuffixed(0))]),
+            punct('.'),
+            ident("saturating_sub"),
+            parens([ident("counter"), punct('.'), ident("count")]),
+        ],
+    ));
+
+    // let (before, after) = match f.align() { ... };
+    body.extend([
+        ident("let"),
+        parens([ident("before"), punct(','), ident("after")]),
+        punct('='),
+        ident("match"),
+        ident("f"),
+        punct('.'),
+        ident("align"),
+        empty_parens(),
+        group(Delimiter::Brace, alignment_arms()),
+        punct(';'),
+    ]);
+
+    // let fill = f.fill();
+    body.extend(let_statement(
+        "fill",
+        [ident("f"), punct('.'), ident("fill"), empty_parens()],
+    ));
+
+    body.extend(fill_loop("before"));
+
+    // write(&mut Writer { f: ::core::option::Option::Some(f), remaining, count: 0 }, self, alternate)?;
+    let mut some = crate::option_variant("Some");
+    some.extend([parens([ident("f")])]);
+    let mut output = TokenStream::from_iter([punct('&'), ident("mut")]);
+    output.extend(writer(some));
+    body.extend(write_call(output));
+
+    body.extend(fill_loop("after"));
+
+    // return ::core::result::Result::Ok(());
+    body.extend([ident("return")]);
+    body.extend(core_path(&["result", "Result"]));
+    body.extend(colons());
+    body.extend([ident("Ok"), parens([empty_parens()]), punct(';')]);
+
+    // if f.width().is_some() || f.precision().is_some() { ... }
+    TokenStream::from_iter([
+        ident("if"),
+        ident("f"),
+        punct('.'),
+        ident("width"),
+        empty_parens(),
+        punct('.'),
+        ident("is_some"),
+        empty_parens(),
+        joint('|'),
+        punct('|'),
+        ident("f"),
+        punct('.'),
+        ident("precision"),
+        empty_parens(),
+        punct('.'),
+        ident("is_some"),
+        empty_parens(),
+        group(Delimiter::Brace, body),
+    ])
+}
+
+/// The writer that counts the characters, and stops after `remaining` characters. With `f`, it also writes them
+///
+/// ```ignore
+/// struct Writer<'a, 'b> {
+///     f: ::core::option::Option<&'a mut ::core::fmt::Formatter<'b>>,
+///     remaining: ::core::primitive::usize,
+///     count: ::core::primitive::usize,
+/// }
+/// ```
+fn writer_struct() -> TokenStream {
+    let mut fields = TokenStream::from_iter([ident("f"), punct(':')]);
+    fields.extend(crate::option_path());
+    fields.extend([punct('<'), punct('&')]);
+    fields.extend(lifetime("a"));
+    fields.extend([ident("mut")]);
+    fields.extend(crate::fmt_path("Formatter"));
+    fields.extend([punct('<')]);
+    fields.extend(lifetime("b"));
+    fields.extend([punct('>'), punct('>'), punct(',')]);
+    for name in ["remaining", "count"] {
+        fields.extend([ident(name), punct(':')]);
+        fields.extend(core_path(&["primitive", "usize"]));
+        fields.extend([punct(',')]);
+    }
+
+    let mut output = TokenStream::from_iter([ident("struct"), ident("Writer"), punct('<')]);
+    output.extend(lifetime("a"));
+    output.extend([punct(',')]);
+    output.extend(lifetime("b"));
+    output.extend([punct('>'), group(Delimiter::Brace, fields)]);
+    output
+}
+
+/// ```ignore
+/// impl ::core::fmt::Write for Writer<'_, '_> {
+///     fn write_str(&mut self, s: &::core::primitive::str) -> ::core::fmt::Result {
+///         let end = s.char_indices().nth(self.remaining).map_or(s.len(), |(end, _)| end);
+///         let s = &s[..end];
+///         let count = s.chars().count();
+///         self.remaining -= count;
+///         self.count += count;
+///         match &mut self.f {
+///             ::core::option::Option::Some(f) => f.write_str(s),
+///             ::core::option::Option::None => ::core::result::Result::Ok(()),
+///         }
+///     }
+/// }
+/// ```
+fn writer_impl() -> TokenStream {
+    let mut body = TokenStream::new();
+
+    // let end = s.char_indices().nth(self.remaining).map_or(s.len(), |(end, _)| end);
+    body.extend(let_statement(
+        "end",
+        [
+            ident("s"),
+            punct('.'),
+            ident("char_indices"),
+            empty_parens(),
+            punct('.'),
+            ident("nth"),
+            parens([ident("self"), punct('.'), ident("remaining")]),
+            punct('.'),
+            ident("map_or"),
+            parens([
+                ident("s"),
+                punct('.'),
+                ident("len"),
+                empty_parens(),
+                punct(','),
+                punct('|'),
+                parens([ident("end"), punct(','), ident("_")]),
+                punct('|'),
+                ident("end"),
+            ]),
+        ],
+    ));
+
+    // let s = &s[..end];
+    body.extend(let_statement(
+        "s",
+        [
+            punct('&'),
+            ident("s"),
+            group(Delimiter::Bracket, [joint('.'), punct('.'), ident("end")]),
+        ],
+    ));
+
+    // let count = s.chars().count();
+    body.extend(let_statement(
+        "count",
+        [
+            ident("s"),
+            punct('.'),
+            ident("chars"),
+            empty_parens(),
+            punct('.'),
+            ident("count"),
+            empty_parens(),
+        ],
+    ));
+
+    // self.remaining -= count;
+    // self.count += count;
+    for (field, operator) in [("remaining", '-'), ("count", '+')] {
+        body.extend([
+            ident("self"),
+            punct('.'),
+            ident(field),
+            joint(operator),
+            punct('='),
+            ident("count"),
+            punct(';'),
+        ]);
+    }
+
+    // ::core::option::Option::Some(f) => f.write_str(s),
+    let mut arms = crate::option_variant("Some");
+    arms.extend([parens([ident("f")])]);
+    arms.extend(arrow());
+    arms.extend([
+        ident("f"),
+        punct('.'),
+        ident("write_str"),
+        parens([ident("s")]),
+        punct(','),
+    ]);
+
+    // ::core::option::Option::None => ::core::result::Result::Ok(()),
+    arms.extend(crate::option_variant("None"));
+    arms.extend(arrow());
+    arms.extend(core_path(&["result", "Result"]));
+    arms.extend(colons());
+    arms.extend([ident("Ok"), parens([empty_parens()]), punct(',')]);
+
+    // match &mut self.f { ... }
+    body.extend([
+        ident("match"),
+        punct('&'),
+        ident("mut"),
+        ident("self"),
+        punct('.'),
+        ident("f"),
+        group(Delimiter::Brace, arms),
+    ]);
+
+    // fn write_str(&mut self, s: &::core::primitive::str) -> ::core::fmt::Result { ... }
+    let mut parameters = TokenStream::from_iter([
+        punct('&'),
+        ident("mut"),
+        ident("self"),
+        punct(','),
+        ident("s"),
+        punct(':'),
+        punct('&'),
+    ]);
+    parameters.extend(core_path(&["primitive", "str"]));
+
+    let mut method = TokenStream::from_iter([ident("fn"), ident("write_str"), parens(parameters)]);
+    method.extend(arrow_result());
+    method.extend([group(Delimiter::Brace, body)]);
+
+    // impl ::core::fmt::Write for Writer<'_, '_> { ... }
+    let mut output = TokenStream::from_iter([ident("impl")]);
+    output.extend(crate::fmt_path("Write"));
+    output.extend([ident("for"), ident("Writer"), punct('<')]);
+    output.extend(lifetime("_"));
+    output.extend([punct(',')]);
+    output.extend(lifetime("_"));
+    output.extend([punct('>'), group(Delimiter::Brace, method)]);
+    output
+}
+
+/// Writes the message into the `Writer`, keeping the alternate flag `{:#}` from the original formatter
+///
+/// `format_args!("{}", value)` calls `Display::fmt` again, but with a fresh formatter without the width
+/// and precision, which writes the message as-is. For `Debug`, the format strings are `"{:?}"` and `"{:#?}"`
+///
+/// ```ignore
+/// fn write<T: ?::core::marker::Sized + ::core::fmt::Display>(
+///     writer: &mut Writer<'_, '_>,
+///     value: &T,
+///     alternate: ::core::primitive::bool,
+/// ) -> ::core::fmt::Result {
+///     if alternate {
+///         ::core::fmt::write(writer, ::core::format_args!("{:#}", value))
+///     } else {
+///         ::core::fmt::write(writer, ::core::format_args!("{}", value))
+///     }
+/// }
+/// ```
+fn write_fn(trait_name: &str) -> TokenStream {
+    let (regular, alternate) = if trait_name == "Debug" {
+        ("{:?}", "{:#?}")
+    } else {
+        ("{}", "{:#}")
+    };
+
+    // ::core::fmt::write(writer, ::core::format_args!("{}", value))
+    let write = |format: &str| {
+        let mut arguments = TokenStream::from_iter([ident("writer"), punct(',')]);
+        arguments.extend(colons());
+        arguments.extend([
+            ident("core"),
+            joint(':'),
+            punct(':'),
+            ident("format_args"),
+            punct('!'),
+            parens([
+                TokenTree::Literal(Literal::string(format)),
+                punct(','),
+                ident("value"),
+            ]),
+        ]);
+
+        let mut call = TokenStream::from_iter(crate::fmt_path("write"));
+        call.extend([parens(arguments)]);
+        call
+    };
+
+    // <T: ?::core::marker::Sized + ::core::fmt::Display>
+    let mut generics = TokenStream::from_iter([ident("T"), punct(':'), punct('?')]);
+    generics.extend(core_path(&["marker", "Sized"]));
+    generics.extend([punct('+')]);
+    generics.extend(crate::fmt_path(trait_name));
+
+    // (writer: &mut Writer, value: &T, alternate: ::core::primitive::bool)
+    let mut parameters = TokenStream::from_iter([
+        ident("writer"),
+        punct(':'),
+        punct('&'),
+        ident("mut"),
+        ident("Writer"),
+        punct('<'),
+    ]);
+    parameters.extend(lifetime("_"));
+    parameters.extend([punct(',')]);
+    parameters.extend(lifetime("_"));
+    parameters.extend([
+        punct('>'),
+        punct(','),
+        ident("value"),
+        punct(':'),
+        punct('&'),
+        ident("T"),
+        punct(','),
+        ident("alternate"),
+        punct(':'),
+    ]);
+    parameters.extend(core_path(&["primitive", "bool"]));
+
+    let mut output = TokenStream::from_iter([ident("fn"), ident("write"), punct('<')]);
+    output.extend(generics);
+    output.extend([punct('>'), parens(parameters)]);
+    output.extend(arrow_result());
+    output.extend([group(
+        Delimiter::Brace,
+        [
+            ident("if"),
+            ident("alternate"),
+            group(Delimiter::Brace, write(alternate)),
+            ident("else"),
+            group(Delimiter::Brace, write(regular)),
+        ],
+    )]);
+    output
+}
+
+/// The amount of fill before and after the message
+///
+/// ```ignore
+/// ::core::option::Option::Some(::core::fmt::Alignment::Right) => (padding, 0),
+/// ::core::option::Option::Some(::core::fmt::Alignment::Center) => (padding / 2, padding - padding / 2),
+/// _ => (0, padding),
+/// ```
+fn alignment_arms() -> TokenStream {
+    let zero = || TokenTree::Literal(Literal::usize_unsuffixed(0));
+    let two = || TokenTree::Literal(Literal::usize_unsuffixed(2));
+
+    let mut arms = TokenStream::new();
+    for (alignment, fill) in [
+        (
+            "Right",
+            TokenStream::from_iter([ident("padding"), punct(','), zero()]),
+        ),
+        (
+            "Center",
+            TokenStream::from_iter([
+                ident("padding"),
+                punct('/'),
+                two(),
+                punct(','),
+                ident("padding"),
+                punct('-'),
+                ident("padding"),
+                punct('/'),
+                two(),
+            ]),
+        ),
+    ] {
+        let mut alignment_path = TokenStream::from_iter(crate::fmt_path("Alignment"));
+        alignment_path.extend(colons());
+        alignment_path.extend([ident(alignment)]);
+
+        arms.extend(crate::option_variant("Some"));
+        arms.extend([parens(alignment_path)]);
+        arms.extend(arrow());
+        arms.extend([parens(fill), punct(',')]);
+    }
+
+    arms.extend([ident("_")]);
+    arms.extend(arrow());
+    arms.extend([parens([zero(), punct(','), ident("padding")]), punct(',')]);
+    arms
+}
+
+/// `for _ in 0..$count { ::core::fmt::Write::write_char(f, fill)?; }`
+fn fill_loop(count: &str) -> TokenStream {
+    let mut body = TokenStream::from_iter(crate::fmt_path("Write"));
+    body.extend(colons());
+    body.extend([
+        ident("write_char"),
+        parens([ident("f"), punct(','), ident("fill")]),
+        punct('?'),
+        punct(';'),
+    ]);
+
+    TokenStream::from_iter([
+        ident("for"),
+        ident("_"),
+        ident("in"),
+        TokenTree::Literal(Literal::usize_unsuffixed(0)),
+        joint('.'),
+        punct('.'),
+        ident(count),
+        group(Delimiter::Brace, body),
+    ])
+}
+
+/// `Writer { f: $f, remaining, count: 0 }`
+fn writer(f: TokenStream) -> TokenStream {
+    let mut fields = TokenStream::from_iter([ident("f"), punct(':')]);
+    fields.extend(f);
+    fields.extend([
+        punct(','),
+        ident("remaining"),
+        punct(','),
+        ident("count"),
+        punct(':'),
+        TokenTree::Literal(Literal::usize_unsuffixed(0)),
+    ]);
+
+    TokenStream::from_iter([ident("Writer"), group(Delimiter::Brace, fields)])
+}
+
+/// `write($writer, self, alternate)?;`
+fn write_call(writer: impl IntoIterator<Item = TokenTree>) -> TokenStream {
+    let mut arguments = TokenStream::from_iter(writer);
+    arguments.extend([punct(','), ident("self"), punct(','), ident("alternate")]);
+
+    TokenStream::from_iter([ident("write"), parens(arguments), punct('?'), punct(';')])
+}
+
+/// `let $name = $value;`
+fn let_statement(name: &str, value: impl IntoIterator<Item = TokenTree>) -> TokenStream {
+    let mut statement = TokenStream::from_iter([ident("let"), ident(name), punct('=')]);
+    statement.extend(value);
+    statement.extend([punct(';')]);
+    statement
+}
+
+/// `::core::$path`
+fn core_path(path: &[&str]) -> Vec<TokenTree> {
+    let mut tokens = colons().to_vec();
+    tokens.push(ident("core"));
+    for segment in path {
+        tokens.extend(colons());
+        tokens.push(ident(segment));
+    }
+    tokens
+}
+
+/// `-> ::core::fmt::Result`
+fn arrow_result() -> TokenStream {
+    let mut tokens = TokenStream::from_iter([joint('-'), punct('>')]);
+    tokens.extend(crate::fmt_path("Result"));
+    tokens
+}
+
+/// `=>`
+fn arrow() -> [TokenTree; 2] {
+    [joint('='), punct('>')]
+}
+
+/// `::`
+fn colons() -> [TokenTree; 2] {
+    [joint(':'), punct(':')]
+}
+
+/// `'$name`
+fn lifetime(name: &str) -> [TokenTree; 2] {
+    [joint('\''), ident(name)]
+}
+
+/// `()`
+fn empty_parens() -> TokenTree {
+    group(Delimiter::Parenthesis, [])
+}
+
+/// `($inner)`
+fn parens(inner: impl IntoIterator<Item = TokenTree>) -> TokenTree {
+    group(Delimiter::Parenthesis, inner)
+}
+
+fn group(delimiter: Delimiter, inner: impl IntoIterator<Item = TokenTree>) -> TokenTree {
+    TokenTree::Group(Group::new(delimiter, inner.into_iter().collect()))
+}
+
+fn ident(name: &str) -> TokenTree {
+    TokenTree::Ident(Ident::new(name, Span::call_site()))
+}
+
+fn punct(ch: char) -> TokenTree {
+    TokenTree::Punct(Punct::new(ch, Spacing::Alone))
+}
+
+/// Punctuation that is the first part of an operator like `::` or `=>`
+fn joint(ch: char) -> TokenTree {
+    TokenTree::Punct(Punct::new(ch, Spacing::Joint))
+}
@@ -0,0 +1,63 @@
+//! Compares the `f.write_str` generated for templates without placeholders
+//! with the `f.write_fmt(format_args!(...))` that was generated before
+//!
+//! There's no measurable difference: `write_fmt` already writes text-only templates with a single `write_str`
+//!
+//! Run with `cargo test --release --test bench -- --ignored --nocapture`
+
+use std::fmt::{self, Write as _};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use displaystr::display;
+
+#[display]
+pub enum Generated {
+    Unknown = "unknown data store error",
+}
+
+pub enum Handwritten {
+    Unknown,
+}
+
+impl fmt::Display for Handwritten {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown => f.write_fmt(format_args!("unknown data store error")),
+        }
+    }
+}
+
+const ITERATIONS: u32 = 1_000_000;
+
+const ROUNDS: usize = 5;
+
+fn measure(value: &dyn fmt::Display) -> Duration {
+    let mut buf = String::with_capacity(64);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        buf.clear();
+        write!(buf, "{}", black_box(value)).unwrap();
+        black_box(&buf);
+    }
+    start.elapsed()
+}
+
+fn nanos(total: Duration) -> f64 {
+    total.as_secs_f64() * 1e9 / f64::from(ITERATIONS)
+}
+
+#[test]
+#[ignore = "benchmark"]
+fn write_str_vs_write_fmt() {
+    // Rounds are interleaved and the fastest one is kept, to reduce the noise
+    let mut write_str = Duration::MAX;
+    let mut write_fmt = Duration::MAX;
+    for _ in 0..ROUNDS {
+        write_str = write_str.min(measure(&Generated::Unknown));
+        write_fmt = write_fmt.min(measure(&Handwritten::Unknown));
+    }
+
+    println!("write_str: {:.1}ns per iteration", nanos(write_str));
+    println!("write_fmt: {:.1}ns per iteration", nanos(write_fmt));
+}
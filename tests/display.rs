@@ -5,6 +5,7 @@ use displaystr::display;
 fn ui() {
     let harness = trybuild::TestCases::new();
     harness.compile_fail("tests/ui/*.rs");
+    harness.pass("tests/pass/*.rs");
 }
 
 #[test]
@@ -81,3 +82,1250 @@ fn struct_variant() {
         "tuple 2 with trailing comma: 4, b"
     );
 }
+
+#[test]
+fn structs() {
+    #[display(doc, "unit struct")]
+    struct Unit;
+
+    #[display("tuple struct: {_0}, {_1}")]
+    struct Tuple(u32, String);
+
+    #[display("named struct: {first}, {}", second)]
+    struct Named {
+        first: u32,
+        second: String,
+    }
+
+    assert_eq!(Unit.to_string(), "unit struct");
+    assert_eq!(Tuple(1, "a".to_string()).to_string(), "tuple struct: 1, a");
+    assert_eq!(
+        Named {
+            first: 2,
+            second: "b".to_string()
+        }
+        .to_string(),
+        "named struct: 2, b"
+    );
+}
+
+#[test]
+fn generics() {
+    #[display]
+    enum Generic<'a, T: core::fmt::Display = u8, const N: usize = 4>
+    where
+        T: Clone,
+    {
+        Borrowed(&'a str) = "borrowed: {_0}",
+        Owned { value: T } = "owned: {value}",
+        Array([u8; N]) = "array: {_0:?}",
+    }
+
+    #[display("wrapper: {_0}")]
+    struct Wrapper<T>(T)
+    where
+        T: core::fmt::Display;
+
+    assert_eq!(Generic::<'_, u8>::Borrowed("a").to_string(), "borrowed: a");
+    let owned: Generic<'_, i32> = Generic::Owned { value: 1 };
+    assert_eq!(owned.to_string(), "owned: 1");
+    assert_eq!(
+        Generic::<'_, u8, 2>::Array([1, 2]).to_string(),
+        "array: [1, 2]"
+    );
+    assert_eq!(Wrapper(1).to_string(), "wrapper: 1");
+}
+
+#[test]
+fn inferred_bounds() {
+    #[display]
+    enum Inferred<A, B, C, D> {
+        Display { value: A } = "display: {value}",
+        Debug(Vec<B>) = "debug: {_0:?}",
+        LowerHex(C) = ("lower hex: {:x}", _0),
+        Unused(D) = "unused",
+    }
+
+    #[display("wrapper: {_0}")]
+    struct Wrapper<T>(T);
+
+    // `T` has no bounds, because `Wrapper<T>` is not `Display`
+    #[display(bound = "T: core::fmt::Debug", "explicit: {:?}", _0.0)]
+    struct Explicit<T>(Wrapper<T>);
+
+    // `D` is not used in any template, so it doesn't need to implement anything
+    struct NotDisplay;
+
+    type Concrete = Inferred<u8, u8, u8, NotDisplay>;
+
+    assert_eq!(Concrete::Display { value: 1 }.to_string(), "display: 1");
+    assert_eq!(Concrete::Debug(vec![1, 2]).to_string(), "debug: [1, 2]");
+    assert_eq!(Concrete::LowerHex(255).to_string(), "lower hex: ff");
+    assert_eq!(Concrete::Unused(NotDisplay).to_string(), "unused");
+    assert_eq!(Wrapper(1).to_string(), "wrapper: 1");
+    assert_eq!(Explicit(Wrapper(1)).to_string(), "explicit: 1");
+}
+
+#[test]
+fn field_types() {
+    use std::collections::HashMap;
+
+    #[display]
+    enum FieldTypes {
+        Map(HashMap<String, u32>) = "map: {_0:?}",
+        Closure(Box<dyn Fn(u8, u8) -> u8>, u8) = ("closure: {}", _0(*_1, 1)),
+        Nested(Result<Box<dyn Fn() -> u8>, Vec<u8>>, Vec<Vec<u8>>) = ("nested: {:?}", _1),
+        Pointer(fn(u8) -> u8, <u8 as core::ops::Add>::Output) = ("pointer: {}", _0(*_1)),
+        Named {
+            map: HashMap<String, Box<dyn Fn() -> u8>>,
+            value: <u8 as core::ops::Add>::Output,
+        } = ("named: {value}, {}", map.len()),
+    }
+
+    assert_eq!(FieldTypes::Map(HashMap::new()).to_string(), "map: {}");
+    assert_eq!(
+        FieldTypes::Closure(Box::new(|a, b| a + b), 2).to_string(),
+        "closure: 3"
+    );
+    assert_eq!(
+        FieldTypes::Nested(Err(vec![]), vec![vec![1]]).to_string(),
+        "nested: [[1]]"
+    );
+    assert_eq!(FieldTypes::Pointer(|a| a * 2, 2).to_string(), "pointer: 4");
+    assert_eq!(
+        FieldTypes::Named {
+            map: HashMap::new(),
+            value: 1
+        }
+        .to_string(),
+        "named: 1, 0"
+    );
+}
+
+#[test]
+fn generic_params() {
+    #[display]
+    enum Nested<T: Iterator<Item = u8>, U: Into<Vec<Vec<u8>>> = Vec<Vec<u8>>> {
+        Iterator(T) = ("iterator: {}", _0.size_hint().0),
+        Into(U) = "into",
+    }
+
+    #[display]
+    enum Function<F: Fn() -> u8, G = fn() -> u8>
+    where
+        G: Fn() -> u8,
+    {
+        F(F) = ("function: {}", _0()),
+        G(G) = ("default: {}", _0()),
+    }
+
+    #[display]
+    enum ConstDefault<const N: usize = { 1 + 2 }> {
+        Array([u8; N]) = "array: {_0:?}",
+    }
+
+    #[display]
+    enum HigherRanked<F: for<'a> Fn(&'a str) -> &'a str> {
+        Trim(F) = ("trimmed: {}", _0(" a ")),
+    }
+
+    #[display("nested struct: {_0}")]
+    struct NestedStruct<T: Into<Vec<Vec<u8>>>, const N: usize = { 1 + 2 }>(u8, [T; N]);
+
+    assert_eq!(
+        Nested::<_>::Iterator([1_u8, 2].into_iter()).to_string(),
+        "iterator: 2"
+    );
+    assert_eq!(
+        Nested::<core::iter::Empty<u8>>::Into(vec![]).to_string(),
+        "into"
+    );
+    assert_eq!(Function::<_>::F(|| 1).to_string(), "function: 1");
+    assert_eq!(Function::<fn() -> u8>::G(|| 2).to_string(), "default: 2");
+    assert_eq!(
+        ConstDefault::<3>::Array([1, 2, 3]).to_string(),
+        "array: [1, 2, 3]"
+    );
+    assert_eq!(HigherRanked::Trim(str::trim).to_string(), "trimmed: a");
+    assert_eq!(
+        NestedStruct::<Vec<Vec<u8>>>(1, [vec![], vec![], vec![]]).to_string(),
+        "nested struct: 1"
+    );
+}
+
+#[test]
+#[deny(deprecated, unused_variables, unused_assignments)]
+fn unused_fields() {
+    #[allow(dead_code)]
+    #[display(deny_unused_fields)]
+    enum UnusedFields {
+        Used {
+            first: u32,
+            second: u32,
+        } = ("{first} {}", second + 1),
+        Underscore {
+            _first: u32,
+        } = "underscore",
+        Marked {
+            #[display(unused)]
+            first: u32,
+        } = "marked",
+        Tuple(#[display(unused)] u32, u32) = "tuple: {_1}",
+    }
+
+    #[allow(dead_code)]
+    #[display(warn_unused_fields, "{_0}")]
+    struct Struct(u32, #[display(unused)] u32);
+
+    assert_eq!(
+        UnusedFields::Used {
+            first: 1,
+            second: 2
+        }
+        .to_string(),
+        "1 3"
+    );
+    assert_eq!(
+        UnusedFields::Underscore { _first: 1 }.to_string(),
+        "underscore"
+    );
+    assert_eq!(UnusedFields::Marked { first: 1 }.to_string(), "marked");
+    assert_eq!(UnusedFields::Tuple(1, 2).to_string(), "tuple: 2");
+    assert_eq!(Struct(1, 2).to_string(), "1");
+}
+
+#[test]
+fn error() {
+    use std::error::Error as _;
+    use std::io;
+
+    #[derive(Debug)]
+    #[display(error)]
+    enum DataStoreError {
+        Disconnect(#[from] io::Error) = "data store disconnected",
+        Redaction(String, #[source] io::Error) = "the data for key `{_0}` is not available",
+        InvalidHeader { found: String, source: io::Error } = "invalid header: {found}",
+        Unknown = "unknown data store error",
+    }
+
+    #[derive(Debug)]
+    #[display(error, "timed out")]
+    struct Timeout;
+
+    #[derive(Debug)]
+    #[display(error)]
+    enum Generic<T> {
+        Inner(#[source] T) = "inner error",
+    }
+
+    let io_error = || io::Error::other("io");
+
+    assert_eq!(
+        DataStoreError::Disconnect(io_error())
+            .source()
+            .map(ToString::to_string),
+        Some("io".to_string())
+    );
+    assert_eq!(
+        DataStoreError::Redaction("key".to_string(), io_error())
+            .source()
+            .map(ToString::to_string),
+        Some("io".to_string())
+    );
+    assert_eq!(
+        DataStoreError::InvalidHeader {
+            found: "header".to_string(),
+            source: io_error()
+        }
+        .source()
+        .map(ToString::to_string),
+        Some("io".to_string())
+    );
+    assert!(DataStoreError::Unknown.source().is_none());
+    assert!(Timeout.source().is_none());
+    assert_eq!(
+        Generic::Inner(Generic::Inner(io_error()))
+            .source()
+            .map(ToString::to_string),
+        Some("inner error".to_string())
+    );
+
+    let _: &dyn std::error::Error = &Timeout;
+}
+
+#[test]
+fn from() {
+    use std::io;
+    use std::num::ParseIntError;
+
+    #[derive(Debug)]
+    #[display(error)]
+    enum DataStoreError {
+        Disconnect(#[from] io::Error) = "data store disconnected",
+        Parse {
+            #[from]
+            source: ParseIntError,
+        } = "invalid number: {source}",
+    }
+
+    #[derive(Debug)]
+    #[display(error, "timed out")]
+    struct Timeout(#[from] io::Error);
+
+    // `From` doesn't need `Error`
+    #[display(from)]
+    enum Plain {
+        Disconnect(#[from] io::Error) = "disconnected: {_0}",
+    }
+
+    fn parse(s: &str) -> Result<u32, DataStoreError> {
+        Ok(s.parse::<u32>()?)
+    }
+
+    let io_error = || io::Error::other("io");
+
+    assert_eq!(
+        DataStoreError::from(io_error()).to_string(),
+        "data store disconnected"
+    );
+    assert_eq!(
+        parse("x").unwrap_err().to_string(),
+        "invalid number: invalid digit found in string"
+    );
+    assert_eq!(Timeout::from(io_error()).to_string(), "timed out");
+    assert_eq!(Plain::from(io_error()).to_string(), "disconnected: io");
+}
+
+#[test]
+fn thiserror_from() {
+    use std::error::Error as _;
+    use std::io;
+
+    // `thiserror` implements `From` and `Error`, the derive is expanded before `#[display]`
+    #[derive(thiserror::Error, Debug)]
+    #[display]
+    pub enum DataStoreError {
+        Disconnect(#[from] io::Error) = "data store disconnected",
+        Parse {
+            #[source]
+            source: std::num::ParseIntError,
+        } = "invalid number: {source}",
+    }
+
+    let disconnect = DataStoreError::from(io::Error::other("io"));
+    assert_eq!(disconnect.to_string(), "data store disconnected");
+    assert_eq!(disconnect.source().unwrap().to_string(), "io");
+
+    let parse = DataStoreError::Parse {
+        source: "x".parse::<u8>().unwrap_err(),
+    };
+    assert_eq!(
+        parse.to_string(),
+        "invalid number: invalid digit found in string"
+    );
+    assert!(parse.source().is_some());
+}
+
+#[test]
+fn transparent() {
+    use std::error::Error as _;
+    use std::io;
+
+    #[derive(Debug)]
+    #[display(error)]
+    enum Inner {
+        Io(#[from] io::Error) = "inner",
+    }
+
+    #[derive(Debug)]
+    #[display(error)]
+    enum Outer {
+        Inner(#[from] Inner) = transparent,
+    }
+
+    #[display]
+    enum Transparent<T> {
+        #[display(transparent)]
+        Number {
+            value: f64,
+        },
+        Generic(T) = transparent,
+    }
+
+    #[display(transparent)]
+    struct Wrapper(u32);
+
+    let outer = || Outer::Inner(Inner::Io(io::Error::other("io")));
+
+    assert_eq!(outer().to_string(), "inner");
+    assert_eq!(
+        outer().source().map(ToString::to_string),
+        Some("io".to_string())
+    );
+    assert_eq!(
+        format!("{:.2}", Transparent::<u8>::Number { value: 1.0 }),
+        "1.00"
+    );
+    assert_eq!(format!("{:>4}", Transparent::Generic(7)), "   7");
+    assert_eq!(format!("{:03}", Wrapper(7)), "007");
+}
+
+#[test]
+fn from_str() {
+    #[derive(Debug, PartialEq)]
+    #[display(from_str)]
+    pub enum Event {
+        Connected { host: String, port: u16 } = "connected to {host}:{port}",
+        Retry(u32) = "retry #{_0} of {_0}",
+        Ratio(f64) = transparent,
+        Closed = "closed",
+        Escaped = "{{closed}}",
+    }
+
+    #[derive(Debug, PartialEq)]
+    #[display(from_str, "{_0}..{_1}")]
+    struct Range<T>(T, T);
+
+    let events = [
+        Event::Connected {
+            host: "localhost".to_string(),
+            port: 8080,
+        },
+        Event::Retry(3),
+        Event::Ratio(0.5),
+        Event::Closed,
+        Event::Escaped,
+    ];
+
+    for event in events {
+        assert_eq!(event.to_string().parse::<Event>(), Ok(event));
+    }
+
+    assert_eq!("1..20".parse::<Range<u8>>(), Ok(Range(1, 20)));
+    assert_eq!("1..2..3".parse::<Range<u8>>().ok(), None);
+
+    let error = "closed!".parse::<Event>().unwrap_err();
+    assert_eq!(
+        error.variants,
+        ["Connected", "Retry", "Ratio", "Closed", "Escaped"]
+    );
+    assert_eq!(
+        error.to_string(),
+        "failed to parse `Event`, expected one of: Connected, Retry, Ratio, Closed, Escaped"
+    );
+}
+
+#[test]
+fn message_template() {
+    #[display(message_template)]
+    pub enum DataStoreError<T> {
+        Redaction(String) = "the data for key `{_0}` is not available",
+        Recovered(String, Vec<String>) = ("recovered {}", _1.join("+")),
+        Value(T) = transparent,
+        Unknown = r"unknown {{error}}",
+    }
+
+    #[display(message_template, "timed out after {ms}ms")]
+    struct Timeout {
+        ms: u64,
+    }
+
+    assert_eq!(
+        DataStoreError::<u8>::Redaction("key".to_string()).message_template(),
+        "the data for key `{_0}` is not available"
+    );
+    assert_eq!(
+        DataStoreError::<u8>::Recovered("key".to_string(), Vec::new()).message_template(),
+        "recovered {}"
+    );
+    assert_eq!(DataStoreError::Value(1).message_template(), "{_0}");
+    assert_eq!(
+        DataStoreError::<u8>::Unknown.message_template(),
+        "unknown {{error}}"
+    );
+    assert_eq!(
+        Timeout { ms: 10 }.message_template(),
+        "timed out after {ms}ms"
+    );
+}
+
+#[test]
+fn introspect() {
+    #[allow(dead_code)]
+    #[display(introspect, message_template)]
+    pub enum DataStoreError {
+        Disconnect(std::io::Error) = "data store disconnected",
+        Redaction(String) = "the data for key `{_0}` is not available",
+        Unknown = "unknown data store error",
+    }
+
+    #[display(introspect, "timed out")]
+    struct Timeout;
+
+    assert_eq!(
+        DataStoreError::VARIANTS,
+        ["Disconnect", "Redaction", "Unknown"]
+    );
+    assert_eq!(
+        DataStoreError::MESSAGES,
+        [
+            "data store disconnected",
+            "the data for key `{_0}` is not available",
+            "unknown data store error"
+        ]
+    );
+    assert_eq!(
+        DataStoreError::Redaction("key".to_string()).variant_name(),
+        "Redaction"
+    );
+    assert_eq!(
+        DataStoreError::Unknown.message_template(),
+        DataStoreError::MESSAGES[2]
+    );
+    assert_eq!(Timeout::VARIANTS, ["Timeout"]);
+    assert_eq!(Timeout.variant_name(), "Timeout");
+}
+
+#[test]
+fn code() {
+    #[allow(dead_code)]
+    #[display]
+    pub enum ApiError {
+        NotFound(String) = ["E0404", "resource {_0} not found"],
+        #[display(code = "E0403")]
+        Forbidden {
+            user: String,
+        } = "{user} is not allowed",
+        Mismatch(u32, u32) = ["E0400", "expected {}, found {}", _0, _1],
+        Io(std::io::Error) = ["E0500", transparent],
+    }
+
+    #[display(show_code)]
+    pub enum ShownError {
+        NotFound(String) = ["E0404", "resource {_0} not found"],
+        #[display(transparent, code = "E0500")]
+        Io(std::io::Error),
+    }
+
+    #[display(code = "E0408", show_code, "timed out after {ms}ms")]
+    struct Timeout {
+        ms: u64,
+    }
+
+    assert_eq!(ApiError::NotFound("key".to_string()).code(), "E0404");
+    assert_eq!(
+        ApiError::NotFound("key".to_string()).to_string(),
+        "resource key not found"
+    );
+    assert_eq!(
+        ApiError::Forbidden {
+            user: "root".to_string()
+        }
+        .code(),
+        "E0403"
+    );
+    assert_eq!(ApiError::Mismatch(1, 2).to_string(), "expected 1, found 2");
+    assert_eq!(ApiError::Mismatch(1, 2).code(), "E0400");
+    assert_eq!(
+        ApiError::Io(std::io::Error::other("disk full")).code(),
+        "E0500"
+    );
+
+    assert_eq!(
+        ShownError::NotFound("key".to_string()).to_string(),
+        "[E0404] resource key not found"
+    );
+    assert_eq!(
+        ShownError::Io(std::io::Error::other("disk full")).to_string(),
+        "[E0500] disk full"
+    );
+    assert_eq!(Timeout { ms: 10 }.code(), "E0408");
+    assert_eq!(
+        Timeout { ms: 10 }.to_string(),
+        "[E0408] timed out after 10ms"
+    );
+}
+
+#[test]
+fn plain_text() {
+    /// The strings passed to each `write_str`
+    #[derive(Default)]
+    struct Writes(Vec<String>);
+
+    impl std::fmt::Write for Writes {
+        fn write_str(&mut self, s: &str) -> std::fmt::Result {
+            self.0.push(s.to_string());
+            Ok(())
+        }
+    }
+
+    fn writes(value: impl std::fmt::Display) -> Vec<String> {
+        let mut writes = Writes::default();
+        std::fmt::write(&mut writes, format_args!("{value}")).unwrap();
+        writes.0
+    }
+
+    #[display(show_code)]
+    pub enum Status {
+        Braces = ["E0001", "unknown {{data}} store }}error{{"],
+        Escapes = ["E0002", "tab\there\nnewline \u{1F600} \x41"],
+        Raw = ["E0003", r#"raw "{{quoted}}""#],
+        Empty = ["E0004", ""],
+        Field(u8) = ["E0005", "field {_0}"],
+    }
+
+    #[display("{{}}")]
+    struct Braces;
+
+    assert_eq!(
+        Status::Braces.to_string(),
+        "[E0001] unknown {data} store }error{"
+    );
+    assert_eq!(
+        Status::Escapes.to_string(),
+        "[E0002] tab\there\nnewline \u{1F600} A"
+    );
+    assert_eq!(Status::Raw.to_string(), r#"[E0003] raw "{quoted}""#);
+    assert_eq!(Status::Empty.to_string(), "[E0004] ");
+    assert_eq!(Braces.to_string(), "{}");
+    assert_eq!(format!("{:>4}", Braces), "{}");
+
+    // Without placeholders, the code and the text are written at once
+    assert_eq!(
+        writes(Status::Braces),
+        ["[E0001] unknown {data} store }error{"]
+    );
+    assert_eq!(writes(Status::Empty), ["[E0004] "]);
+    assert_eq!(writes(Braces), ["{}"]);
+    assert_eq!(writes(Status::Field(1)), ["[E0005] ", "field ", "1"]);
+}
+
+#[test]
+fn pad() {
+    /// Writes more with `{:#}`
+    struct Verbose;
+
+    impl std::fmt::Display for Verbose {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str(if f.alternate() { "verbose" } else { "short" })
+        }
+    }
+
+    #[display(pad)]
+    pub enum Level {
+        Info = "info",
+        Warning(u32) = "warning #{_0}",
+        Unicode = "ünïcødé",
+        Io(std::io::Error) = transparent,
+        Verbose(Verbose) = transparent,
+    }
+
+    #[display(pad, "timed out after {ms}ms")]
+    struct Timeout {
+        ms: u64,
+    }
+
+    assert_eq!(Level::Info.to_string(), "info");
+    assert_eq!(format!("{:>8}|", Level::Info), "    info|");
+    assert_eq!(format!("{:<8}|", Level::Info), "info    |");
+    assert_eq!(format!("{:8}|", Level::Info), "info    |");
+    assert_eq!(format!("{:^9}|", Level::Info), "  info   |");
+    assert_eq!(format!("{:*^12}", Level::Warning(7)), "*warning #7*");
+    assert_eq!(format!("{:.4}", Level::Warning(7)), "warn");
+    assert_eq!(format!("{:>6.3}|", Level::Warning(7)), "   war|");
+    assert_eq!(format!("{:>9}|", Level::Unicode), "  ünïcødé|");
+    assert_eq!(format!("{:.3}", Level::Unicode), "ünï");
+    assert_eq!(format!("{:2}", Level::Warning(7)), "warning #7");
+    assert_eq!(
+        format!("{:-<12}", Level::Io(std::io::Error::other("disk"))),
+        "disk--------"
+    );
+    assert_eq!(format!("{:>8}|", Level::Verbose(Verbose)), "   short|");
+    assert_eq!(format!("{:>#8}|", Level::Verbose(Verbose)), " verbose|");
+    assert_eq!(format!("{:#.4}", Level::Verbose(Verbose)), "verb");
+    assert_eq!(
+        format!("{:>24}", Timeout { ms: 10 }),
+        "    timed out after 10ms"
+    );
+}
+
+#[test]
+fn alternate() {
+    #[display(deny_unused_fields)]
+    pub enum RequestError {
+        Timeout {
+            ms: u64,
+        } = (
+            "timed out",
+            alt = "the request timed out after {ms}ms; consider raising the limit",
+        ),
+        Status(u16, String) = ("status {}", _0, alt = ("status {}: {}", _0, _1)),
+        Unchanged = "unchanged",
+    }
+
+    #[display]
+    pub enum Cancelled {
+        Cancelled = ["E0499", "cancelled", alt = "cancelled {{by the user}}"],
+    }
+
+    #[display("{ms}ms", alt = "timed out after {ms}ms")]
+    struct Timeout {
+        ms: u64,
+    }
+
+    let timeout = RequestError::Timeout { ms: 10 };
+    assert_eq!(timeout.to_string(), "timed out");
+    assert_eq!(
+        format!("{timeout:#}"),
+        "the request timed out after 10ms; consider raising the limit"
+    );
+
+    let status = RequestError::Status(404, "not found".to_string());
+    assert_eq!(status.to_string(), "status 404");
+    assert_eq!(format!("{status:#}"), "status 404: not found");
+
+    assert_eq!(Cancelled::Cancelled.to_string(), "cancelled");
+    assert_eq!(
+        format!("{:#}", Cancelled::Cancelled),
+        "cancelled {by the user}"
+    );
+    assert_eq!(Cancelled::Cancelled.code(), "E0499");
+    assert_eq!(format!("{:#}", RequestError::Unchanged), "unchanged");
+
+    assert_eq!(Timeout { ms: 10 }.to_string(), "10ms");
+    assert_eq!(format!("{:#}", Timeout { ms: 10 }), "timed out after 10ms");
+
+    #[display(pad)]
+    pub enum Padded {
+        Timeout { ms: u64 } = ("timed out", alt = "timed out after {ms}ms"),
+    }
+
+    let timeout = Padded::Timeout { ms: 10 };
+    assert_eq!(format!("{timeout:>12}|"), "   timed out|");
+    assert_eq!(format!("{timeout:>#24}|"), "    timed out after 10ms|");
+    assert_eq!(format!("{timeout:-<#24}|"), "timed out after 10ms----|");
+    assert_eq!(format!("{timeout:#.9}"), "timed out");
+}
+
+#[test]
+fn debug() {
+    #[display(debug, pad)]
+    pub enum DataStoreError<T> {
+        Redaction(String) = "the data for key `{_0}` is not available",
+        #[display(debug = "Value({_0:?})")]
+        Value(T) = "invalid value",
+        Timeout {
+            ms: u64,
+        } = ("timed out", alt = "timed out after {ms}ms"),
+        Io(std::io::Error) = transparent,
+    }
+
+    #[display(debug = "Timeout {{ ms: {ms} }}", "timed out after {ms}ms")]
+    struct Timeout {
+        ms: u64,
+    }
+
+    #[display(debug, error)]
+    pub enum ConfigError {
+        Missing(String) = "missing key {_0}",
+    }
+
+    assert_eq!(
+        format!("{:?}", DataStoreError::<u8>::Redaction("key".to_string())),
+        "the data for key `key` is not available"
+    );
+    assert_eq!(
+        format!("{:?}", DataStoreError::Value(vec![1])),
+        "Value([1])"
+    );
+    assert_eq!(DataStoreError::Value(vec![1]).to_string(), "invalid value");
+    assert_eq!(
+        format!("{:?}", DataStoreError::<u8>::Timeout { ms: 10 }),
+        "timed out"
+    );
+    assert_eq!(
+        format!("{:#?}", DataStoreError::<u8>::Timeout { ms: 10 }),
+        "timed out after 10ms"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            DataStoreError::<u8>::Io(std::io::Error::other("disk full"))
+        ),
+        "disk full"
+    );
+    assert_eq!(
+        format!("{:>12?}|", DataStoreError::Value(1)),
+        "    Value(1)|"
+    );
+    assert_eq!(
+        format!("{:>#24?}|", DataStoreError::<u8>::Timeout { ms: 10 }),
+        "    timed out after 10ms|"
+    );
+
+    assert_eq!(format!("{:?}", Timeout { ms: 10 }), "Timeout { ms: 10 }");
+    assert_eq!(Timeout { ms: 10 }.to_string(), "timed out after 10ms");
+
+    let error: Box<dyn std::error::Error> = Box::new(ConfigError::Missing("port".to_string()));
+    assert_eq!(format!("{error:?}"), "missing key port");
+}
+
+#[test]
+fn prefix_suffix() {
+    #[derive(Debug, PartialEq)]
+    #[display(prefix = "config error: ", suffix = ".", from_str)]
+    pub enum ConfigError {
+        Missing(String) = "missing key {_0}",
+        Invalid { key: String } = ("invalid key {key}", alt = "the key {key} is invalid"),
+        Empty = "empty {{file}}",
+        Port(u16) = transparent,
+    }
+
+    #[derive(Debug, PartialEq)]
+    #[display(show_code, prefix = "api: ", from_str)]
+    pub enum ApiError {
+        NotFound(String) = ["E0404", "resource {_0} not found"],
+    }
+
+    #[display(suffix = "!", "timed out after {ms}ms")]
+    struct Timeout {
+        ms: u64,
+    }
+
+    assert_eq!(
+        ConfigError::Missing("port".to_string()).to_string(),
+        "config error: missing key port."
+    );
+    let invalid = ConfigError::Invalid {
+        key: "port".to_string(),
+    };
+    assert_eq!(invalid.to_string(), "config error: invalid key port.");
+    assert_eq!(
+        format!("{invalid:#}"),
+        "config error: the key port is invalid."
+    );
+    assert_eq!(
+        ConfigError::Empty.to_string(),
+        "config error: empty {file}."
+    );
+    assert_eq!(ConfigError::Port(80).to_string(), "config error: 80.");
+
+    assert_eq!(
+        "config error: missing key port.".parse(),
+        Ok(ConfigError::Missing("port".to_string()))
+    );
+    assert_eq!("config error: 80.".parse(), Ok(ConfigError::Port(80)));
+    assert!("missing key port.".parse::<ConfigError>().is_err());
+
+    let not_found = ApiError::NotFound("key".to_string());
+    assert_eq!(not_found.to_string(), "[E0404] api: resource key not found");
+    assert_eq!(not_found.to_string().parse(), Ok(not_found));
+
+    assert_eq!(Timeout { ms: 10 }.to_string(), "timed out after 10ms!");
+}
+
+#[test]
+fn wrap() {
+    #[derive(Debug, PartialEq)]
+    #[display(wrap = "[{variant}] {message} (see {{docs}})", from_str)]
+    pub enum ConfigError {
+        Missing(String) = "missing key {_0}",
+        Empty = "the file is empty",
+        Port(u16) = transparent,
+    }
+
+    #[display(
+        show_code,
+        prefix = "api: ",
+        wrap = "{variant}: {message}",
+        suffix = "."
+    )]
+    pub enum ApiError {
+        NotFound(String) = ["E0404", "resource {_0} not found"],
+    }
+
+    #[display(wrap = "<{variant}> {message}", "timed out after {ms}ms")]
+    struct Timeout {
+        ms: u64,
+    }
+
+    assert_eq!(
+        ConfigError::Missing("port".to_string()).to_string(),
+        "[Missing] missing key port (see {docs})"
+    );
+    assert_eq!(
+        ConfigError::Empty.to_string(),
+        "[Empty] the file is empty (see {docs})"
+    );
+    assert_eq!(ConfigError::Port(80).to_string(), "[Port] 80 (see {docs})");
+    assert_eq!(
+        "[Missing] missing key port (see {docs})".parse(),
+        Ok(ConfigError::Missing("port".to_string()))
+    );
+
+    assert_eq!(
+        ApiError::NotFound("key".to_string()).to_string(),
+        "NotFound: [E0404] api: resource key not found."
+    );
+    assert_eq!(
+        Timeout { ms: 10 }.to_string(),
+        "<Timeout> timed out after 10ms"
+    );
+}
+
+#[test]
+fn default_case() {
+    #[allow(dead_code)]
+    #[display(default_case = "snake_case")]
+    pub enum Snake {
+        NotFound,
+        HTTPStatus2Ok(u16),
+        Timeout { ms: u64 },
+        Explicit = "explicitly {{written}}",
+    }
+
+    #[allow(non_camel_case_types)]
+    #[display(default_case = "kebab-case")]
+    pub enum Kebab {
+        NotFound,
+        Already_Snake,
+    }
+
+    #[display(default_case = "Title Case", introspect)]
+    pub enum Title {
+        NotFound,
+        IOError,
+    }
+
+    #[allow(dead_code)]
+    #[display(default_case = "lowercase words", deny_unused_fields)]
+    pub enum LowerWords {
+        ConnectionReset { peer: String },
+        Last,
+    }
+
+    #[display(default_case = "Title Case")]
+    struct RequestTimedOut;
+
+    assert_eq!(Snake::NotFound.to_string(), "not_found");
+    assert_eq!(Snake::HTTPStatus2Ok(200).to_string(), "http_status2_ok");
+    assert_eq!(Snake::Timeout { ms: 10 }.to_string(), "timeout");
+    assert_eq!(Snake::Explicit.to_string(), "explicitly {written}");
+
+    assert_eq!(Kebab::NotFound.to_string(), "not-found");
+    assert_eq!(Kebab::Already_Snake.to_string(), "already-snake");
+
+    assert_eq!(Title::NotFound.to_string(), "Not Found");
+    assert_eq!(Title::IOError.to_string(), "Io Error");
+    assert_eq!(Title::MESSAGES, ["Not Found", "Io Error"]);
+
+    assert_eq!(
+        LowerWords::ConnectionReset {
+            peer: "localhost".to_string()
+        }
+        .to_string(),
+        "connection reset"
+    );
+    assert_eq!(LowerWords::Last.to_string(), "last");
+
+    assert_eq!(RequestTimedOut.to_string(), "Request Timed Out");
+}
+
+#[test]
+fn pretty_doc() {
+    #[display(doc = "pretty")]
+    pub enum DataStoreError {
+        Redaction(String) = "the data for key {_0:?} is not available",
+        InvalidHeader {
+            expected: String,
+            found: String,
+        } = r#"invalid header (expected "{expected}", found "{found}")"#,
+        Timeout {
+            ms: u64,
+        } = ("timed out after {}s\n{{retrying}}", ms / 1000),
+    }
+
+    #[display(doc = "pretty", "timed out after {ms}ms")]
+    struct Timeout {
+        ms: u64,
+    }
+
+    assert_eq!(
+        DataStoreError::Redaction("key".to_string()).to_string(),
+        "the data for key \"key\" is not available"
+    );
+    assert_eq!(
+        DataStoreError::InvalidHeader {
+            expected: "a".to_string(),
+            found: "b".to_string()
+        }
+        .to_string(),
+        r#"invalid header (expected "a", found "b")"#
+    );
+    assert_eq!(
+        DataStoreError::Timeout { ms: 2000 }.to_string(),
+        "timed out after 2s\n{retrying}"
+    );
+    assert_eq!(Timeout { ms: 10 }.to_string(), "timed out after 10ms");
+}
+
+#[test]
+fn from_doc() {
+    #[allow(dead_code)]
+    #[display(from_doc)]
+    pub enum DataStoreError {
+        /// the data for key `{0}` is not available
+        Redaction(String),
+        /**
+         * invalid header
+         * (expected {expected:?}, found {found:?})
+         *
+         * The whole comment is the message
+         */
+        InvalidHeader { expected: String, found: String },
+        /**
+         * timed out after {ms}ms, {{retrying}}
+         */
+        Timeout { ms: u64 },
+        /// The explicit message is used instead
+        Explicit = "explicit",
+    }
+
+    /// timed out after {ms}ms
+    #[display(from_doc)]
+    struct Timeout {
+        ms: u64,
+    }
+
+    #[display(from_doc, ignore_extra_doc_attributes)]
+    pub enum Extra {
+        /// only the first line
+        /// is the message
+        First,
+    }
+
+    assert_eq!(
+        DataStoreError::Redaction("key".to_string()).to_string(),
+        "the data for key `key` is not available"
+    );
+    assert_eq!(
+        DataStoreError::InvalidHeader {
+            expected: "a".to_string(),
+            found: "b".to_string()
+        }
+        .to_string(),
+        "invalid header\n(expected \"a\", found \"b\")\n\nThe whole comment is the message"
+    );
+    assert_eq!(
+        DataStoreError::Timeout { ms: 10 }.to_string(),
+        "timed out after 10ms, {retrying}"
+    );
+    assert_eq!(DataStoreError::Explicit.to_string(), "explicit");
+    assert_eq!(Timeout { ms: 10 }.to_string(), "timed out after 10ms");
+    assert_eq!(Extra::First.to_string(), "only the first line");
+}
+
+#[test]
+fn attribute_template() {
+    #[derive(Debug)]
+    #[display(error, doc)]
+    pub enum DataStoreError {
+        #[display(code = "E0100", "data store disconnected")]
+        Disconnect(std::io::Error),
+        #[display(code = "E0404", "the data for key `{_0}` is not available")]
+        Redaction(String),
+        #[display(code = "E0408", "timed out after {}s", ms / 1000)]
+        Timeout {
+            ms: u64,
+        },
+        Explicit = ["E0500", "explicit"],
+    }
+
+    #[derive(Debug)]
+    #[display(error)]
+    pub enum ThisError {
+        #[error(transparent)]
+        Io(std::io::Error),
+        #[error("the data for key `{0}` is not available")]
+        Redaction(String),
+        #[error("invalid header (expected {expected:?}, found {found:?})")]
+        InvalidHeader { expected: String, found: String },
+        #[error("{} of {}: {:?}", .0 + 1, .1.len(), (.2, *.2..10))]
+        Positional(u8, String, u8),
+        #[error("{}", .message.to_uppercase())]
+        Field { message: String },
+    }
+
+    assert_eq!(
+        DataStoreError::Disconnect(std::io::Error::other("eof")).to_string(),
+        "data store disconnected"
+    );
+    assert_eq!(
+        DataStoreError::Redaction("key".to_string()).to_string(),
+        "the data for key `key` is not available"
+    );
+    assert_eq!(DataStoreError::Redaction(String::new()).code(), "E0404");
+    assert_eq!(
+        DataStoreError::Timeout { ms: 2000 }.to_string(),
+        "timed out after 2s"
+    );
+    assert_eq!(DataStoreError::Explicit.to_string(), "explicit");
+
+    assert_eq!(
+        ThisError::Io(std::io::Error::other("eof")).to_string(),
+        "eof"
+    );
+    assert_eq!(
+        ThisError::Redaction("key".to_string()).to_string(),
+        "the data for key `key` is not available"
+    );
+    assert_eq!(
+        ThisError::InvalidHeader {
+            expected: "a".to_string(),
+            found: "b".to_string()
+        }
+        .to_string(),
+        r#"invalid header (expected "a", found "b")"#
+    );
+    assert_eq!(
+        ThisError::Positional(1, "ab".to_string(), 3).to_string(),
+        "2 of 2: (3, 3..10)"
+    );
+    assert_eq!(
+        ThisError::Field {
+            message: "loud".to_string()
+        }
+        .to_string(),
+        "LOUD"
+    );
+}
+
+#[test]
+fn derive() {
+    #[derive(Debug, displaystr::Display)]
+    #[display(error, introspect)]
+    #[repr(u8)]
+    pub enum DataStoreError {
+        #[display("data store disconnected")]
+        Disconnect(#[from] std::io::Error) = 1,
+        #[display("the data for key `{_0}` is not available")]
+        Redaction(String),
+        #[display(transparent)]
+        Other(std::fmt::Error) = 5,
+        #[error("invalid header (expected {expected:?}, found {found:?})")]
+        InvalidHeader { expected: String, found: String },
+        #[error(transparent)]
+        Parse(std::num::ParseIntError),
+    }
+
+    #[derive(displaystr::Display)]
+    #[display(from_doc)]
+    pub enum Documented<T> {
+        /// got {0}
+        Value(T),
+    }
+
+    /// timed out after {ms}ms
+    #[derive(displaystr::Display)]
+    #[display(from_doc)]
+    struct Timeout {
+        ms: u64,
+    }
+
+    #[derive(displaystr::Display)]
+    #[display("{_0} and {_1}")]
+    struct Pair(u8, u8);
+
+    let disconnect = DataStoreError::from(std::io::Error::other("eof"));
+    assert_eq!(disconnect.to_string(), "data store disconnected");
+    assert!(std::error::Error::source(&disconnect).is_some());
+    assert_eq!(
+        DataStoreError::Redaction("key".to_string()).to_string(),
+        "the data for key `key` is not available"
+    );
+    assert_eq!(
+        DataStoreError::Other(std::fmt::Error).to_string(),
+        std::fmt::Error.to_string()
+    );
+    assert_eq!(
+        DataStoreError::MESSAGES,
+        [
+            "data store disconnected",
+            "the data for key `{_0}` is not available",
+            "{_0}",
+            "invalid header (expected {expected:?}, found {found:?})",
+            "{_0}",
+        ]
+    );
+    assert_eq!(
+        DataStoreError::InvalidHeader {
+            expected: "a".to_string(),
+            found: "b".to_string(),
+        }
+        .to_string(),
+        r#"invalid header (expected "a", found "b")"#
+    );
+    let parse = "x".parse::<u8>().unwrap_err();
+    assert_eq!(
+        DataStoreError::Parse(parse.clone()).to_string(),
+        parse.to_string()
+    );
+    assert_eq!(Documented::Value(1).to_string(), "got 1");
+    assert_eq!(Timeout { ms: 10 }.to_string(), "timed out after 10ms");
+    assert_eq!(Pair(1, 2).to_string(), "1 and 2");
+}
+
+#[test]
+// names close to a field only get a warning, because they can be captured
+#[allow(deprecated)]
+fn captured_names() {
+    const MAX_LEN: usize = 10;
+    #[allow(non_upper_case_globals)]
+    static unit: &str = "ms";
+    const ID: &str = "store";
+    const UNIT: &str = "seconds";
+
+    #[display]
+    enum Limits {
+        TooLong = "longer than {MAX_LEN} characters",
+        Timeout { ms: u64 } = "timed out after {ms}{unit}",
+        Missing { id: u32 } = "missing {id} in {ID}",
+        Interval { units: u64 } = "every {units} {UNIT}",
+    }
+
+    assert_eq!(Limits::TooLong.to_string(), "longer than 10 characters");
+    assert_eq!(Limits::Timeout { ms: 5 }.to_string(), "timed out after 5ms");
+    assert_eq!(Limits::Missing { id: 1 }.to_string(), "missing 1 in store");
+    assert_eq!(Limits::Interval { units: 5 }.to_string(), "every 5 seconds");
+}
+
+#[test]
+fn boxed_source() {
+    use std::error::Error;
+    use std::io;
+
+    #[derive(Debug)]
+    #[display(error)]
+    enum DataStoreError {
+        Other(#[source] Box<dyn Error + Send + Sync>) = "other error",
+        Boxed { source: std::boxed::Box<dyn Error> } = "boxed error",
+        Transparent(Box<dyn Error + Send + Sync + 'static>) = transparent,
+    }
+
+    let boxed = || Box::new(io::Error::other("io"));
+
+    assert_eq!(
+        DataStoreError::Other(boxed())
+            .source()
+            .map(ToString::to_string),
+        Some("io".to_string())
+    );
+    assert_eq!(
+        DataStoreError::Boxed { source: boxed() }
+            .source()
+            .map(ToString::to_string),
+        Some("io".to_string())
+    );
+    assert_eq!(DataStoreError::Transparent(boxed()).to_string(), "io");
+    assert!(DataStoreError::Transparent(boxed()).source().is_none());
+}
@@ -0,0 +1,15 @@
+#![no_std]
+
+// Links `std` for the runtime without making the `::std` path available
+extern crate std as _;
+
+#[displaystr::display(from_str)]
+pub enum Status {
+    Ok = "ok",
+    Code { code: u16 } = "code {code}",
+}
+
+fn main() {
+    assert!(matches!("ok".parse(), Ok(Status::Ok)));
+    assert!("code".parse::<Status>().is_err());
+}
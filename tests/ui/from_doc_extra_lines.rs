@@ -0,0 +1,16 @@
+#[displaystr::display(from_doc)]
+enum DataStoreError {
+    /// invalid header (expected {expected:?}, found {found:?})
+    /// in the data store
+    InvalidHeader { expected: String, found: String },
+}
+
+/// timed out after {ms}ms
+///
+/// Retrying might help
+#[displaystr::display(from_doc)]
+struct Timeout {
+    ms: u64,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+#[displaystr::display]
+enum Alternate {
+    NotLast(u32) = ("short", alt = "long {_0}", _0),
+    NotString = ("short", alt = 5),
+    UnknownField = ("short", alt = "long {missing}"),
+}
+
+fn main() {}
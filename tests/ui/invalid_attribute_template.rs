@@ -0,0 +1,23 @@
+#[displaystr::display]
+enum Both {
+    #[display("from the attribute")]
+    NotFound = "from the discriminant",
+    Other = "other",
+}
+
+#[displaystr::display]
+enum Transparent {
+    #[display(transparent)]
+    #[error("message")]
+    Io(std::io::Error),
+}
+
+#[displaystr::display]
+enum Error {
+    #[error]
+    Missing,
+    #[error(42)]
+    Number,
+}
+
+fn main() {}
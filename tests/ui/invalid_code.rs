@@ -0,0 +1,17 @@
+#[displaystr::display]
+enum Duplicate {
+    NotFound(String) = ["E0404", "resource {_0} not found"],
+    #[display(code = "E0404")]
+    Gone = "gone",
+    Missing = "missing",
+    #[display(code = "E0400")]
+    Both = ["E0401", "both"],
+    Empty = ["E0402"],
+}
+
+#[displaystr::display(show_code)]
+enum NoCodes {
+    Unit = "unit",
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+#[displaystr::display(debug = "enum")]
+enum EnumLevel {
+    Unit = "unit",
+}
+
+#[displaystr::display]
+enum Override {
+    #[display(debug = "{missing}")]
+    UnknownField(u32) = "{_0}",
+    #[display(debug)]
+    MissingString = "missing",
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[displaystr::display(default_case = "camelCase")]
+enum Status {
+    NotFound,
+}
+
+fn main() {}
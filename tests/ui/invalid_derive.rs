@@ -0,0 +1,19 @@
+#[derive(displaystr::Display)]
+#[display(doc)]
+enum Doc {
+    #[display("not found")]
+    NotFound,
+}
+
+#[derive(displaystr::Display)]
+enum MissingMessage {
+    #[display("not found")]
+    NotFound = 1,
+    Timeout = 2,
+}
+
+#[derive(displaystr::Display)]
+#[display = "not found"]
+struct NotFound;
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[derive(Debug)]
+#[displaystr::display(error)]
+enum DataStoreError {
+    Disconnect(#[from] std::io::Error) = "data store disconnected",
+    Reconnect(#[from] std::io::Error) = "data store reconnected",
+    Redaction(String, #[from] std::fmt::Error) = "the data for key `{_0}` is not available",
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[displaystr::display(from_str)]
+enum Event {
+    Arguments(u32) = ("retry {}", _0 + 1),
+    Debug(u32) = "retry {_0:?}",
+    Adjacent(u32, u32) = "{_0}{_1}",
+    Missing { host: String, port: u16 } = "connected to {host}",
+}
+
+fn main() {}
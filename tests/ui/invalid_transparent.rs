@@ -0,0 +1,17 @@
+#[displaystr::display]
+enum Transparent {
+    Unit = transparent,
+    Pair(u32, u32) = transparent,
+    #[display(transparent)]
+    Both(u32) = "{_0}",
+}
+
+#[displaystr::display(transparent, "{_0}")]
+struct Wrapper(u32);
+
+#[displaystr::display(transparent)]
+enum Forwarded {
+    Io(std::io::Error) = transparent,
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+#[displaystr::display(wrap = "[{variant}]")]
+enum NoMessage {
+    Unit = "unit",
+}
+
+#[displaystr::display(wrap = "{message} {message}")]
+enum TwoMessages {
+    Unit = "unit",
+}
+
+#[displaystr::display(wrap = "{code}: {message:>10}")]
+enum UnknownPlaceholder {
+    Unit = "unit",
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+#[derive(Debug)]
+#[displaystr::display(error)]
+enum DataStoreError {
+    Disconnect(#[source] std::io::Error, #[source] std::io::Error) = "data store disconnected",
+    InvalidHeader { source: std::io::Error, #[from] other: std::io::Error } = "invalid header",
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[displaystr::display]
+struct Timeout {
+    ms: u64,
+}
+
+fn main() {}
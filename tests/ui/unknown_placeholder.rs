@@ -0,0 +1,14 @@
+#[displaystr::display]
+enum DataStoreError {
+    InvalidHeader { expected: String, found: String } = "expected {expectd}, found {found}",
+    Redaction(String) = ("the data for key `{_1}` is not available: {reason}", reason = 1),
+    Unknown = "unknown data store error: {reason:width$}",
+    Missing { id: u32 } = "missing {ID}",
+}
+
+#[displaystr::display("timed out after {milliseconds}ms")]
+struct Timeout {
+    ms: u64,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+#[displaystr::display(deny_unused_fields)]
+enum DataStoreError {
+    InvalidHeader { expected: String, found: String } = "expected {expected}",
+    Redaction(String, Vec<String>) = ("the data is not available, but we recovered: {}", _1.join("+")),
+    Unknown { _reason: String, #[display(unused)] code: u8 } = "unknown data store error",
+}
+
+#[displaystr::display(deny_unused_fields, "timed out")]
+struct Timeout {
+    ms: u64,
+}
+
+fn main() {}
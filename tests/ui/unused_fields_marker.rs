@@ -0,0 +1,6 @@
+#[displaystr::display]
+enum DataStoreError {
+    Unknown { #[display(unknown)] code: u8 } = "unknown data store error",
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+#[displaystr::display(warn_unused_fields)]
+enum DataStoreError {
+    InvalidHeader { expected: String, found: String } = "expected {expected}",
+    Unknown { _reason: String } = "unknown data store error",
+}
+
+#[displaystr::display(warn_unused_fields, "timed out")]
+struct Timeout {
+    ms: u64,
+}
+
+fn main() {
+    // Only programs that fail to compile have their output compared,
+    // so this error makes the warnings above show up
+    let _: () = DataStoreError::Unknown {
+        _reason: String::new(),
+    };
+    let _ = Timeout { ms: 0 };
+}